@@ -0,0 +1,85 @@
+#ifndef GUIDED_DIGRAPH_H
+#define GUIDED_DIGRAPH_H
+
+/* Generated by cbindgen from src/ffi.rs -- do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define GID_ALG_NAIVE 0
+
+#define GID_ALG_SIMPLE 1
+
+#define GID_ALG_BFGT 2
+
+#define GID_ALG_LOG 3
+
+#define GID_ALG_JUMP 4
+
+#define GID_OK 0
+
+#define GID_ERROR -1
+
+// Status of a state; states that have not been seen are GID_OPEN.
+typedef enum GidStatus {
+  GID_LIVE,
+  GID_DEAD,
+  GID_UNKNOWN,
+  GID_OPEN,
+} GidStatus;
+
+// Opaque handle to a state graph
+typedef struct GidGraph GidGraph;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create a new, empty graph using one of the GID_ALG_* algorithms.
+// Returns NULL if the algorithm is not valid.
+struct GidGraph *gid_new(uint32_t alg);
+
+// Free a graph created by gid_new. Does nothing if g is NULL.
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+void gid_free(struct GidGraph *g);
+
+// Add a transition from v1 to v2, where v1 is Open or Live.
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+int32_t gid_add_transition(struct GidGraph *g, size_t v1, size_t v2);
+
+// Mark v closed, where v is Open or Live.
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+int32_t gid_mark_closed(struct GidGraph *g, size_t v);
+
+// Mark v live, where v is Open or Live.
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+int32_t gid_mark_live(struct GidGraph *g, size_t v);
+
+// Status of v (GID_OPEN if v has not been seen, or if g is NULL).
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+enum GidStatus gid_get_status(const struct GidGraph *g, size_t v);
+
+// Whether v is Dead (same as gid_get_status(g, v) == GID_DEAD).
+//
+// # Safety
+// g must be NULL or a graph returned by gid_new that was not yet freed.
+bool gid_is_dead(const struct GidGraph *g, size_t v);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* GUIDED_DIGRAPH_H */
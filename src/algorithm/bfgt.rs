@@ -14,9 +14,14 @@
     simple.rs, which could be improved.
 */
 
+use crate::certificate;
+use crate::checkpoint::Checkpointed;
 use crate::debug_counter::DebugCounter;
+use crate::dot::{self, Overlay};
+use crate::events::StatusEvents;
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{StateGraph, Status, StatusEvent, Vertex};
+use crate::invariants;
 use crate::util::FreshClone;
 use std::collections::{HashMap, HashSet};
 use std::iter;
@@ -25,17 +30,37 @@ use std::iter;
 #[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
 struct Level(usize);
 
-#[derive(Debug, Default)]
-pub struct BFGTStateGraph {
-    graph: DiGraph<usize, (Status, Level)>,
+#[derive(Clone, Debug)]
+pub struct BFGTStateGraph<V = usize> {
+    graph: DiGraph<V, (Status, Level)>,
     // edges from open states, not yet added to the graph
-    pending_edges_fwd: HashMap<usize, Vec<usize>>,
+    pending_edges_fwd: HashMap<V, Vec<V>>,
     // count of graph edges
     edge_counter: usize,
     // Additional time counter for debugging
     additional_time: DebugCounter,
+    // States that were marked live, for live certificates
+    marked_live: HashSet<V>,
+    // Snapshots for push/pop (see checkpoint.rs)
+    checkpoints: Vec<Self>,
+    // Status change events (see events.rs)
+    events: StatusEvents<V>,
 }
-impl BFGTStateGraph {
+impl<V> Default for BFGTStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: Default::default(),
+            pending_edges_fwd: Default::default(),
+            edge_counter: Default::default(),
+            additional_time: Default::default(),
+            marked_live: Default::default(),
+            checkpoints: Default::default(),
+            events: Default::default(),
+        }
+    }
+}
+impl<V: Vertex> BFGTStateGraph<V> {
     /* The core parameter for the algorithm: delta = sqrt(num edges) */
     fn delta(&self) -> usize {
         self.additional_time.inc();
@@ -43,22 +68,23 @@ impl BFGTStateGraph {
     }
 
     /* Vertex label getters / setters */
-    fn set_status(&mut self, v: usize, status: Status) {
+    fn set_status(&mut self, v: V, status: Status) {
         // println!("Setting status: {} {:?}", v, status);
         debug_assert!(self.is_seen(v));
+        self.events.mark(v);
         self.graph.get_label_mut(v).unwrap().0 = status;
     }
-    fn get_level(&self, v: usize) -> Level {
+    fn get_level(&self, v: V) -> Level {
         debug_assert!(self.is_seen(v));
         self.graph.get_label(v).unwrap().1
     }
-    fn set_level(&mut self, v: usize, level: Level) {
+    fn set_level(&mut self, v: V, level: Level) {
         // println!("Setting level: {} {:?}", v, level);
         debug_assert!(self.is_seen(v));
         self.graph.get_label_mut(v).unwrap().1 = level;
     }
 
-    fn update_levels_iterative(&mut self, v1: usize, v2: usize) {
+    fn update_levels_iterative(&mut self, v1: V, v2: V) {
         // println!("Updating levels: {} {}", v1, v2);
         // println!("Graph: {:?}", self.graph);
         // Update levels after adding an edge (v1, v2),
@@ -91,8 +117,10 @@ impl BFGTStateGraph {
         // ===== STEP 2: Search Backward =====
         let mut found_cycle = false;
         let mut count = 0;
+        // The searches return canonical vertices, and v1 may have been merged
+        // by a previous edge from the same state (see mark_closed_unchecked)
         let mut set_bck = HashSet::new();
-        set_bck.insert(v1);
+        set_bck.insert(self.graph.get_canon_vertex(v1));
         for u in self
             .graph
             .dfs_bck(v1, |u| {
@@ -137,7 +165,7 @@ impl BFGTStateGraph {
             };
 
             self.set_level(v2, new_level);
-            let level_to_increase: Vec<usize> = self
+            let level_to_increase: Vec<V> = self
                 .graph
                 .dfs_fwd(v2, |w| {
                     // println!("Step 3 DFS fwd trying: {}", w);
@@ -174,7 +202,7 @@ impl BFGTStateGraph {
             // This part is roughly the same as merge_all_cycles in simple.rs
             let v1 = self.graph.get_canon_vertex(v1);
             let v2 = self.graph.get_canon_vertex(v2);
-            let fwd_reachable: HashSet<usize> = self
+            let fwd_reachable: HashSet<V> = self
                 .graph
                 .dfs_fwd(v2, |w| {
                     debug_assert!(self.get_level(w) >= level1);
@@ -184,7 +212,7 @@ impl BFGTStateGraph {
                 .collect();
             debug_assert!(fwd_reachable.contains(&(v1)));
             debug_assert!(fwd_reachable.contains(&(v2)));
-            let bi_reachable: HashSet<usize> = self
+            let bi_reachable: HashSet<V> = self
                 .graph
                 .dfs_bck(v1, |u| {
                     self.is_unknown(u) && fwd_reachable.contains(&u)
@@ -200,7 +228,7 @@ impl BFGTStateGraph {
 
         // ===== DONE =====
     }
-    fn check_dead_iterative(&mut self, v: usize) {
+    fn check_dead_iterative(&mut self, v: V) {
         // This is the same procedure as in Simple
         for u in self
             .graph
@@ -210,7 +238,7 @@ impl BFGTStateGraph {
             self.set_status(u, Status::Dead);
         }
     }
-    fn calculate_new_live_states(&mut self, v: usize) {
+    fn calculate_new_live_states(&mut self, v: V) {
         // Same fn as in Naive
         if self.is_live(v) {
             for u in
@@ -221,11 +249,20 @@ impl BFGTStateGraph {
         }
     }
 }
-impl StateGraph for BFGTStateGraph {
+impl<V: Vertex> Checkpointed<V> for BFGTStateGraph<V> {
+    fn checkpoints(&mut self) -> &mut Vec<Self> {
+        &mut self.checkpoints
+    }
+    fn events(&mut self) -> &mut StatusEvents<V> {
+        &mut self.events
+    }
+}
+
+impl<V: Vertex> StateGraph<V> for BFGTStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
         self.graph.ensure_vertex(v1);
         self.graph.ensure_vertex(v2);
         debug_assert_eq!(self.get_status(v1), Some(Status::Open));
@@ -234,7 +271,7 @@ impl StateGraph for BFGTStateGraph {
         self.edge_counter += 1;
         self.calculate_new_live_states(v2);
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
+    fn mark_closed_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
         self.set_status(v, Status::Unknown);
         // Add pending fwd-edges
@@ -248,12 +285,79 @@ impl StateGraph for BFGTStateGraph {
         debug_assert!(!self.pending_edges_fwd.contains_key(&v));
         self.check_dead_iterative(v);
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
+        self.marked_live.insert(v);
         self.set_status(v, Status::Live);
         self.calculate_new_live_states(v);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn push(&mut self) {
+        self.push_snapshot();
+    }
+    fn pop(&mut self) {
+        self.pop_snapshot();
+    }
+    fn enable_events(&mut self) {
+        self.events.enable(&self.graph, |&(st, _)| st);
+        self.graph.enable_merge_log();
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        self.events.drain(&self.graph, |&(st, _)| st)
+    }
+    fn drain_merges(&mut self) -> Vec<(V, V)> {
+        self.graph.drain_merges()
+    }
+    fn enable_certificates(&mut self) {
+        self.graph.enable_orig_index();
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::dead_certificate(&self.graph, v, |&(st, _)| st)
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::live_certificate(
+            &self.graph,
+            v,
+            |&(st, _)| st,
+            &self.marked_live,
+        )
+    }
+    fn check_invariants(&self) -> Result<(), String> {
+        invariants::check_statuses(&self.graph, |&(st, _)| st)?;
+        invariants::check_acyclic(&self.graph, |v| self.is_unknown(v))?;
+        // Pseudo-topological order: levels don't decrease along edges from
+        // Unknown states (to states that are not Dead)
+        for v in self.graph.iter_vertices().filter(|&v| self.is_unknown(v)) {
+            for w in self.graph.iter_fwd_edges(v) {
+                if !self.is_dead(w) && self.get_level(v) > self.get_level(w) {
+                    return Err(format!(
+                        "Level of {:?} ({:?}) is above that of its successor \
+                        {:?} ({:?})",
+                        v,
+                        self.get_level(v),
+                        w,
+                        self.get_level(w)
+                    ));
+                }
+            }
+        }
+        // Edges are only pending from states that are not closed
+        for &v in self.pending_edges_fwd.keys() {
+            if self.is_u_or_d(v) {
+                return Err(format!("{:?} is closed but has pending edges", v));
+            }
+        }
+        Ok(())
+    }
+    fn to_dot(&self) -> String {
+        // Level of each state (shared by the states merged with it)
+        let mut overlay = Overlay::default();
+        for v in self.graph.iter_vertices_all() {
+            let level = self.get_level(v).0;
+            overlay.labels.insert(v, format!("level {}", level));
+        }
+        dot::to_dot(&self.graph, |&(st, _)| st, &overlay)
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).map(|l| l.0)
     }
     fn get_space(&self) -> usize {
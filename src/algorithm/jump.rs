@@ -4,30 +4,45 @@
     of states ahead at once.
 */
 
+use crate::certificate;
+use crate::checkpoint::Checkpointed;
 use crate::debug_counter::DebugCounter;
+use crate::dot::{self, Overlay};
+use crate::events::StatusEvents;
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{StateGraph, Status, StatusEvent, Vertex};
+use crate::invariants;
 use crate::util::FreshClone;
 use std::cell::RefCell;
 use std::collections::{HashSet, LinkedList};
 use std::iter;
 
-#[derive(Debug, Default, PartialEq)]
-struct Node {
+#[derive(Clone, Debug, PartialEq)]
+struct Node<V> {
     // Jump list: nonempty for closed vertices.
     // First is a real edge, and the ith is approximately 2^i edges forward.
     // Use interior mutability because it is updated on 'get' operations.
-    jumps: RefCell<Vec<usize>>,
+    jumps: RefCell<Vec<V>>,
 
     // Reserve list: forward edges not added to graph.
-    reserve: LinkedList<usize>,
+    reserve: LinkedList<V>,
 
     // Categorized status, same as in other algorithms
     status: Status,
 }
-fn merge_nodes(mut n1: Node, mut n2: Node) -> Node {
+impl<V> Default for Node<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            jumps: Default::default(),
+            reserve: Default::default(),
+            status: Default::default(),
+        }
+    }
+}
+fn merge_nodes<V>(mut n1: Node<V>, mut n2: Node<V>) -> Node<V> {
     // Note: result will be Status::Open!
-    let mut result: Node = Default::default();
+    let mut result: Node<V> = Default::default();
     debug_assert!(n1.status == Status::Unknown || n1.status == Status::Open);
     debug_assert!(n2.status == Status::Unknown || n2.status == Status::Open);
     debug_assert_eq!(result.status, Status::Open);
@@ -36,25 +51,43 @@ fn merge_nodes(mut n1: Node, mut n2: Node) -> Node {
     result
 }
 
-#[derive(Debug, Default)]
-pub struct JumpStateGraph {
-    graph: DiGraph<usize, Node>,
+#[derive(Clone, Debug)]
+pub struct JumpStateGraph<V = usize> {
+    graph: DiGraph<V, Node<V>>,
     additional_space: DebugCounter,
+    // States that were marked live, for live certificates
+    marked_live: HashSet<V>,
+    // Snapshots for push/pop (see checkpoint.rs)
+    checkpoints: Vec<Self>,
+    // Status change events (see events.rs)
+    events: StatusEvents<V>,
+}
+impl<V> Default for JumpStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: Default::default(),
+            additional_space: Default::default(),
+            marked_live: Default::default(),
+            checkpoints: Default::default(),
+            events: Default::default(),
+        }
+    }
 }
-impl JumpStateGraph {
+impl<V: Vertex> JumpStateGraph<V> {
     /* Node label manipulation */
-    fn get_node(&self, v: usize) -> &Node {
+    fn get_node(&self, v: V) -> &Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label(v).unwrap()
     }
-    fn get_node_mut(&mut self, v: usize) -> &mut Node {
+    fn get_node_mut(&mut self, v: V) -> &mut Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label_mut(v).unwrap()
     }
     // Status getters / setters
-    fn set_status(&mut self, v: usize, status: Status) {
-        // println!("  Set status: {} {:?}", v, status);
+    fn set_status(&mut self, v: V, status: Status) {
         debug_assert!(self.is_seen(v));
+        self.events.mark(v);
         self.get_node_mut(v).status = status;
         // Mark live in particular deletes jumps and reserve edges.
         if status == Status::Live {
@@ -63,39 +96,38 @@ impl JumpStateGraph {
         }
     }
     // Reserve edges getters / setters
-    fn push_reserve(&mut self, v: usize, w: usize) {
+    fn push_reserve(&mut self, v: V, w: V) {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
         self.get_node_mut(v).reserve.push_back(w);
         self.additional_space.inc();
     }
-    fn pop_reserve(&mut self, v: usize) -> Option<usize> {
+    fn pop_reserve(&mut self, v: V) -> Option<V> {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
         self.get_node_mut(v).reserve.pop_back()
     }
     // Jump list getters / setters
-    fn jumps_empty(&self, v: usize) -> bool {
+    fn jumps_empty(&self, v: V) -> bool {
         self.get_node(v).jumps.borrow().is_empty()
     }
-    fn get_nth_jump(&self, v: usize, n: usize) -> usize {
+    fn get_nth_jump(&self, v: V, n: usize) -> V {
         debug_assert!(self.is_closed(v));
         debug_assert!(self.get_node(v).jumps.borrow().len() > n);
         self.get_node(v).jumps.borrow()[n]
     }
-    fn get_first_jump(&self, v: usize) -> usize {
-        // println!("get_first_jump: {} {}", v, self.is_closed(v));
+    fn get_first_jump(&self, v: V) -> V {
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.jumps_empty(v));
         self.get_nth_jump(v, 0)
     }
-    fn get_last_jump(&self, v: usize) -> usize {
+    fn get_last_jump(&self, v: V) -> V {
         // Get the current last element in the jumps list.
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.jumps_empty(v));
         *self.get_node(v).jumps.borrow().last().unwrap()
     }
-    fn get_num_jumps(&self, v: usize) -> usize {
+    fn get_num_jumps(&self, v: V) -> usize {
         // Get the length of the jumps list
         // (open vertices implicitly have no jumps)
         if self.is_closed(v) {
@@ -105,22 +137,19 @@ impl JumpStateGraph {
             0
         }
     }
-    fn pop_last_jump(&self, v: usize) {
+    fn pop_last_jump(&self, v: V) {
         // Remove the current last element in the jumps list.
-        // println!("  Popping last jump: {}", v);
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.jumps_empty(v));
         self.get_node(v).jumps.borrow_mut().pop();
     }
-    fn clear_jumps(&mut self, v: usize) {
-        // println!("  Clearing jumps: {}", v);
+    fn clear_jumps(&mut self, v: V) {
         debug_assert!(self.is_closed(v));
         debug_assert!(!self.jumps_empty(v));
         self.get_node_mut(v).jumps.get_mut().clear();
     }
-    fn push_last_jump(&self, v: usize, w: usize) {
+    fn push_last_jump(&self, v: V, w: V) {
         // Add a last element to the jumps list.
-        // println!("  Pushing jump: {}, {}", v, w);
         debug_assert!(self.is_closed(v));
         self.get_node(v).jumps.borrow_mut().push(w);
         self.additional_space.inc();
@@ -142,7 +171,7 @@ impl JumpStateGraph {
 
         Uses interior mutability to modify the jumps list.
     */
-    fn is_root(&self, v: usize, end: usize) -> bool {
+    fn is_root(&self, v: V, end: V) -> bool {
         debug_assert!(self.is_unknown(v) || self.is_open(v));
         debug_assert!(self.is_open(end));
         if self.is_open(v) {
@@ -167,10 +196,9 @@ impl JumpStateGraph {
         Merge the path from vertex v to the Open vertex it currently points
         to.
     */
-    fn merge_path_from(&mut self, v: usize) {
-        let to_merge: Vec<usize> = {
+    fn merge_path_from(&mut self, v: V) {
+        let to_merge: Vec<V> = {
             iter::successors(Some(v), |&w| {
-                // println!("{} {:?} {}", w, self.get_status(w));
                 if self.is_closed(w) {
                     Some(self.get_first_jump(w))
                 } else {
@@ -180,34 +208,31 @@ impl JumpStateGraph {
             .collect()
         };
         for &w in &to_merge {
-            // println!("  Merging: {}, {}", v, w);
             self.graph.merge_using(v, w, merge_nodes);
         }
     }
 
     /*
-        Initialize function for a newly closed vertex, to find an univisted
+        Initialize function for newly closed vertices, to find an univisted
         vertex.
+        Several vertices can be initialized at once: until it is visited,
+        each one stays open, the same as vertices that are reopened below.
     */
-    fn initialize_jumps(&mut self, v: usize) {
-        // println!("Initializing jumps from: {}", v);
-        let mut to_visit = vec![v];
+    fn initialize_jumps(&mut self, vs: &[V]) {
+        let mut to_visit = vs.to_vec();
         while let Some(x) = to_visit.pop() {
             self.initialize_jumps_step(&mut to_visit, x);
         }
     }
-    fn initialize_jumps_step(&mut self, to_visit: &mut Vec<usize>, v: usize) {
+    fn initialize_jumps_step(&mut self, to_visit: &mut Vec<V>, v: V) {
         while let Some(w) = self.pop_reserve(v) {
             if self.is_dead(w) {
-                // println!("  (dead)");
                 continue;
             } else if self.is_root(w, v) {
                 // Merge cycle and continue
-                // println!("  (merging {} -> {} -> ... -> {})", v, w, w_end);
                 self.merge_path_from(w);
             } else {
                 // No further work, set jump and return
-                // println!("  (setting jump and returning)");
                 debug_assert!(self.jumps_empty(v));
                 self.set_status(v, Status::Unknown);
                 self.graph.ensure_edge_fwd(v, w);
@@ -218,8 +243,7 @@ impl JumpStateGraph {
         // No more edges -- v is dead.
         // Recurse on all edges backwards from v.
         self.set_status(v, Status::Dead);
-        // println!("Found Dead: {}", v);
-        let to_recurse: HashSet<usize> = self
+        let to_recurse: HashSet<V> = self
             .graph
             .iter_bck_edges(v)
             .filter(|&u| self.is_unknown(u))
@@ -228,17 +252,72 @@ impl JumpStateGraph {
         // Set to_recurse as open so that recursive calls won't mess with them;
         // then add them to the visit list
         for &u in &to_recurse {
-            // println!("  Recursing on: {}", u);
             self.clear_jumps(u);
             self.set_status(u, Status::Open);
             to_visit.push(u);
         }
     }
 
+    /*
+        Invariants of the jump lists (see check_invariants)
+        - Open and Live vertices have no jumps.
+        - For an Unknown vertex, the first jump is a real edge, and once
+          Dead jumps are removed, the last jump leads (following last jumps
+          again) to an Open root, as assumed by is_root.
+    */
+    fn check_jumps(&self, v: V) -> Result<(), String> {
+        let num_jumps = self.get_node(v).jumps.borrow().len();
+        if self.is_open(v) || self.is_live(v) {
+            if num_jumps > 0 {
+                return Err(format!("{:?} is not closed but has jumps", v));
+            }
+            return Ok(());
+        } else if self.is_dead(v) {
+            return Ok(());
+        }
+        if num_jumps == 0 {
+            return Err(format!("{:?} is Unknown but has no jumps", v));
+        }
+        let first = self.get_first_jump(v);
+        if !self
+            .graph
+            .iter_fwd_edges(v)
+            .any(|w| self.graph.is_same_vertex(w, first))
+        {
+            return Err(format!(
+                "First jump of {:?} ({:?}) is not an edge",
+                v, first
+            ));
+        }
+        // Each step goes to a different vertex on the path to the root
+        let mut w = v;
+        for _ in self.graph.iter_vertices() {
+            if !self.is_unknown(w) {
+                break;
+            }
+            let jumps = self.get_node(w).jumps.borrow();
+            w = match jumps.iter().rev().find(|&&u| !self.is_dead(u)) {
+                Some(&u) => u,
+                None => {
+                    return Err(format!("All jumps of {:?} are Dead", w));
+                }
+            };
+        }
+        if !self.is_open(w) {
+            return Err(format!(
+                "Jumps from {:?} lead to {:?}, which is {:?}, not Open",
+                v,
+                w,
+                self.get_status(w).unwrap()
+            ));
+        }
+        Ok(())
+    }
+
     /*
         Calculate new live states
     */
-    fn calculate_new_live_states(&mut self, v: usize) {
+    fn calculate_new_live_states(&mut self, v: V) {
         // Same fn as in Naive
         if self.is_live(v) {
             for u in
@@ -249,29 +328,90 @@ impl JumpStateGraph {
         }
     }
 }
-impl StateGraph for JumpStateGraph {
+impl<V: Vertex> Checkpointed<V> for JumpStateGraph<V> {
+    fn checkpoints(&mut self) -> &mut Vec<Self> {
+        &mut self.checkpoints
+    }
+    fn events(&mut self) -> &mut StatusEvents<V> {
+        &mut self.events
+    }
+}
+
+impl<V: Vertex> StateGraph<V> for JumpStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
-        // println!("# Adding transition: {}, {}", v1, v2);
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
         self.graph.ensure_edge_bck(v1, v2);
         self.calculate_new_live_states(v2);
         if !self.is_live(v1) {
             self.push_reserve(v1, v2);
         }
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
-        // println!("# Marking Closed: {}", v);
+    fn mark_closed_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
-        self.initialize_jumps(v);
+        self.initialize_jumps(&[v]);
+    }
+    fn mark_closed_batch_unchecked(&mut self, vs: &[V]) {
+        for &v in vs {
+            self.graph.ensure_vertex(v);
+        }
+        self.initialize_jumps(vs);
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
+        self.marked_live.insert(v);
         self.set_status(v, Status::Live);
         self.calculate_new_live_states(v);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn push(&mut self) {
+        self.push_snapshot();
+    }
+    fn pop(&mut self) {
+        self.pop_snapshot();
+    }
+    fn enable_events(&mut self) {
+        self.events.enable(&self.graph, |node| node.status);
+        self.graph.enable_merge_log();
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        self.events.drain(&self.graph, |node| node.status)
+    }
+    fn drain_merges(&mut self) -> Vec<(V, V)> {
+        self.graph.drain_merges()
+    }
+    fn enable_certificates(&mut self) {
+        self.graph.enable_orig_index();
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::dead_certificate(&self.graph, v, |node| node.status)
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::live_certificate(
+            &self.graph,
+            v,
+            |node| node.status,
+            &self.marked_live,
+        )
+    }
+    fn check_invariants(&self) -> Result<(), String> {
+        invariants::check_statuses(&self.graph, |node| node.status)?;
+        for v in self.graph.iter_vertices() {
+            self.check_jumps(v)?;
+        }
+        Ok(())
+    }
+    fn to_dot(&self) -> String {
+        // Jump lists of Unknown vertices (those of Dead ones are stale)
+        let mut overlay = Overlay::default();
+        for v in self.graph.iter_vertices().filter(|&v| self.is_unknown(v)) {
+            for (i, &w) in self.get_node(v).jumps.borrow().iter().enumerate() {
+                overlay.edges.push((v, w, format!("jump {}", i)));
+            }
+        }
+        dot::to_dot(&self.graph, |node| node.status, &overlay)
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).map(|l| l.status)
     }
     fn get_space(&self) -> usize {
@@ -3,61 +3,108 @@
 
     Implementation that uses Euler tour trees (euler_forest.rs)
     to track which states are in the same component of the forest.
+
+    Removing transitions is supported as long as the source state is not
+    Live and has not been merged into a cycle: if the removed transition is
+    the successor edge, we remove it from the forest and look for a new
+    successor as if the state had just been closed.
+    (Live and merged states don't keep enough information to do this.)
 */
 
+use crate::certificate;
+use crate::checkpoint::Checkpointed;
 use crate::debug_counter::DebugCounter;
+use crate::dot::{self, Overlay};
 use crate::euler_forest::EulerForest;
+use crate::events::StatusEvents;
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{StateGraph, Status, StatusEvent, Vertex};
+use crate::invariants;
 use crate::util::FreshClone;
 use std::collections::{HashSet, LinkedList};
 use std::iter;
 use std::mem;
 
-#[derive(Debug, Default, PartialEq)]
-struct Node {
+#[derive(Clone, Debug, PartialEq)]
+struct Node<V> {
     // Reserve list: forward edges not added to graph.
-    reserve: LinkedList<usize>,
+    reserve: LinkedList<V>,
 
     // Successor
     // Stored as an edge, rather than just a vertex,
     // to preserve the original ID in case of vertex merging.
-    next: Option<(usize, usize)>,
+    next: Option<(V, V)>,
 
     // Categorized status, same as in other algorithms
     status: Status,
+
+    // Whether the node is the result of merging a cycle
+    merged: bool,
 }
-fn merge_nodes(mut n1: Node, mut n2: Node) -> Node {
+impl<V> Default for Node<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            reserve: Default::default(),
+            next: Default::default(),
+            status: Default::default(),
+            merged: false,
+        }
+    }
+}
+fn merge_nodes<V>(mut n1: Node<V>, mut n2: Node<V>) -> Node<V> {
     // Note: result will be Status::Open!
-    let mut result: Node = Default::default();
+    let mut result: Node<V> = Default::default();
     debug_assert!(n1.status == Status::Unknown || n1.status == Status::Open);
     debug_assert!(n2.status == Status::Unknown || n2.status == Status::Open);
     debug_assert_eq!(result.status, Status::Open);
     result.reserve.append(&mut n1.reserve);
     result.reserve.append(&mut n2.reserve);
+    result.merged = true;
     result
 }
 
-#[derive(Debug, Default)]
-pub struct LogStateGraph {
-    graph: DiGraph<usize, Node>,
+#[derive(Clone, Debug)]
+pub struct LogStateGraph<V = usize> {
+    graph: DiGraph<V, Node<V>>,
+    // The Euler forest is indexed by the unique IDs that the graph assigns
+    // to each vertex (see euler_id below)
     euler_forest: EulerForest,
     additional_space: DebugCounter,
+    // States that were marked live, for live certificates
+    marked_live: HashSet<V>,
+    // Snapshots for push/pop (see checkpoint.rs)
+    checkpoints: Vec<Self>,
+    // Status change events (see events.rs)
+    events: StatusEvents<V>,
+}
+impl<V> Default for LogStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: Default::default(),
+            euler_forest: Default::default(),
+            additional_space: Default::default(),
+            marked_live: Default::default(),
+            checkpoints: Default::default(),
+            events: Default::default(),
+        }
+    }
 }
-impl LogStateGraph {
+impl<V: Vertex> LogStateGraph<V> {
     /* Node label manipulation */
-    fn get_node(&self, v: usize) -> &Node {
+    fn get_node(&self, v: V) -> &Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label(v).unwrap()
     }
-    fn get_node_mut(&mut self, v: usize) -> &mut Node {
+    fn get_node_mut(&mut self, v: V) -> &mut Node<V> {
         debug_assert!(self.is_seen(v));
         self.graph.get_label_mut(v).unwrap()
     }
     // Status getters / setters
-    fn set_status(&mut self, v: usize, status: Status) {
-        // println!("  Set status: {} {:?}", v, status);
+    fn set_status(&mut self, v: V, status: Status) {
         debug_assert!(self.is_seen(v));
+        self.events.mark(v);
         self.get_node_mut(v).status = status;
         // Mark live in particular deletes reserve edges.
         if status == Status::Live {
@@ -65,28 +112,43 @@ impl LogStateGraph {
         }
     }
     // Reserve edges getters / setters
-    fn push_reserve(&mut self, v: usize, w: usize) {
+    fn push_reserve(&mut self, v: V, w: V) {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
         self.additional_space.inc();
         self.get_node_mut(v).reserve.push_back(w);
     }
-    fn pop_reserve(&mut self, v: usize) -> Option<usize> {
+    fn pop_reserve(&mut self, v: V) -> Option<V> {
         debug_assert!(self.is_seen(v));
         debug_assert!(!self.is_closed(v));
         self.get_node_mut(v).reserve.pop_back()
     }
+    fn remove_reserve(&mut self, v: V, w: V) {
+        // Remove all copies of w from the reserve list.
+        // Unlike push and pop, also works for closed vertices.
+        debug_assert!(self.is_seen(v));
+        let vmut = self.get_node_mut(v);
+        vmut.reserve = mem::take(&mut vmut.reserve)
+            .into_iter()
+            .filter(|&u| u != w)
+            .collect();
+    }
+    // Euler forest vertex corresponding to v
+    fn euler_id(&self, v: V) -> usize {
+        debug_assert!(self.is_seen(v));
+        self.graph.get_unique_id(v).unwrap()
+    }
     // In this implementation, every vertex has at most one successor.
-    fn get_succ(&self, v: usize) -> Option<usize> {
+    fn get_succ(&self, v: V) -> Option<V> {
         debug_assert!(self.is_closed(v));
         self.get_node(v).next.map(|(_, w)| w)
     }
-    fn set_succ(&mut self, v: usize, w: usize) {
+    fn set_succ(&mut self, v: V, w: V) {
         debug_assert_eq!(self.get_succ(v), None);
         self.get_node_mut(v).next = Some((v, w));
     }
     // Clear the node's successor and return the edge
-    fn clear_succ(&mut self, v: usize) -> (usize, usize) {
+    fn clear_succ(&mut self, v: V) -> (V, V) {
         debug_assert!(self.get_succ(v).is_some());
         let vmut = self.get_node_mut(v);
         let mut result = None;
@@ -101,10 +163,10 @@ impl LogStateGraph {
         In this implementation, we critically rely
         on Euler tour trees for the efficient check.
     */
-    fn is_root(&self, v: usize, end: usize) -> bool {
+    fn is_root(&self, v: V, end: V) -> bool {
         debug_assert!(self.is_unknown(v) || self.is_open(v));
         debug_assert!(self.is_open(end));
-        self.euler_forest.same_root(v, end)
+        self.euler_forest.same_root(self.euler_id(v), self.euler_id(end))
         // The following naive implementation works too, not using euler_forest
         // if self.is_open(v) {
         //     self.graph.is_same_vertex(v, end)
@@ -116,10 +178,9 @@ impl LogStateGraph {
     /*
         Merge the path from v to the Open vertex it currently points to.
     */
-    fn merge_path_from(&mut self, v: usize) {
-        let to_merge: Vec<usize> = {
+    fn merge_path_from(&mut self, v: V) {
+        let to_merge: Vec<V> = {
             iter::successors(Some(v), |&w| {
-                // println!("{} {:?}", w, self.get_status(w));
                 if self.is_closed(w) {
                     Some(self.get_succ(w).unwrap())
                 } else {
@@ -129,50 +190,47 @@ impl LogStateGraph {
             .collect()
         };
         for &w in &to_merge {
-            // println!("  Merging: {}, {}", v, w);
             self.graph.merge_using(v, w, merge_nodes);
         }
     }
 
     /*
         check_dead:
-        Initialize function for a newly closed vertex, to find an univisted
+        Initialize function for newly closed vertices, to find an univisted
         vertex.
+        As in Jump, several vertices can be initialized at once.
     */
-    fn is_succ(&self, u: usize, v: usize) -> bool {
+    fn is_succ(&self, u: V, v: V) -> bool {
         match self.get_succ(u) {
             Some(w) => self.graph.is_same_vertex(w, v),
             None => false,
         }
     }
-    fn check_dead(&mut self, v: usize) {
-        let mut to_visit = vec![v];
+    fn check_dead(&mut self, vs: &[V]) {
+        let mut to_visit = vs.to_vec();
         while let Some(x) = to_visit.pop() {
             self.check_dead_step(&mut to_visit, x);
         }
     }
-    fn check_dead_step(&mut self, to_visit: &mut Vec<usize>, v: usize) {
+    fn check_dead_step(&mut self, to_visit: &mut Vec<V>, v: V) {
         debug_assert!(self.is_open(v));
         while let Some(w) = self.pop_reserve(v) {
             if self.is_dead(w) {
                 continue;
             } else if self.is_root(w, v) {
                 // Merge cycle and continue
-                // println!("  (merging {} -> {} -> ... -> {})", v, w, w_end);
                 self.merge_path_from(w);
             } else {
                 // No further work, set successor and return
-                // println!("  (setting successor and returning)");
                 self.set_status(v, Status::Unknown);
                 self.set_succ(v, w);
-                self.euler_forest.add_edge(v, w);
+                self.euler_forest.add_edge(self.euler_id(v), self.euler_id(w));
                 return;
             }
         }
         // No more edges -- v is dead.
-        // println!("Found Dead: {}", v);
         // Recurse on all edges backwards from v.
-        let to_recurse: HashSet<usize> = self
+        let to_recurse: HashSet<V> = self
             .graph
             .iter_bck_edges(v)
             .filter(|&u| self.is_unknown(u))
@@ -193,15 +251,100 @@ impl LogStateGraph {
             if first_iter {
                 first_iter = false;
             } else {
-                self.euler_forest.remove_edge(orig_u, orig_v);
+                self.euler_forest
+                    .remove_edge(self.euler_id(orig_u), self.euler_id(orig_v));
+            }
+        }
+    }
+
+    /*
+        Invariants of the successors and the Euler forest that mirrors them
+        (see check_invariants)
+        - Open and Dead vertices have no successor.
+        - Each Unknown vertex has a successor that is not Dead, the edge to
+          it is in the forest, and following successors leads to an Open
+          root that is in the same tree of the forest.
+        - The states merged into a vertex are in the same tree.
+    */
+    fn same_tree(&self, v: V, w: V) -> bool {
+        let (id1, id2) = (self.euler_id(v), self.euler_id(w));
+        self.euler_forest.is_seen(id1)
+            && self.euler_forest.is_seen(id2)
+            && self.euler_forest.same_root(id1, id2)
+    }
+    fn check_forest(&self, v: V) -> Result<(), String> {
+        let node = self.get_node(v);
+        if node.merged {
+            for u in self.graph.iter_merged(v) {
+                if !self.same_tree(u, v) {
+                    return Err(format!(
+                        "{:?} was merged into {:?} but is in another tree",
+                        u, v
+                    ));
+                }
+            }
+        }
+        if self.is_open(v) || self.is_dead(v) {
+            if node.next.is_some() {
+                return Err(format!(
+                    "{:?} is not Unknown but has a successor",
+                    v
+                ));
+            }
+            return Ok(());
+        } else if self.is_live(v) {
+            return Ok(());
+        }
+        let (orig_v, orig_w) = match node.next {
+            Some(edge) => edge,
+            None => {
+                return Err(format!("{:?} is Unknown but has no successor", v))
+            }
+        };
+        if self.is_dead(orig_w) {
+            return Err(format!("Successor of {:?} ({:?}) is Dead", v, orig_w));
+        }
+        if !self.same_tree(orig_v, orig_w) {
+            return Err(format!(
+                "Successor edge {:?} -> {:?} is not in the forest",
+                orig_v, orig_w
+            ));
+        }
+        // Each step goes to a different vertex on the path to the root
+        let mut w = v;
+        for _ in self.graph.iter_vertices() {
+            if !self.is_unknown(w) {
+                break;
             }
+            w = self.get_succ(w).ok_or_else(|| {
+                format!(
+                    "{:?} (on the path from {:?}) is Unknown but has no \
+                    successor",
+                    w, v
+                )
+            })?;
+        }
+        if !self.is_open(w) {
+            return Err(format!(
+                "Successors from {:?} lead to {:?}, which is {:?}, not Open",
+                v,
+                w,
+                self.get_status(w).unwrap()
+            ));
+        }
+        if !self.same_tree(v, w) {
+            return Err(format!(
+                "{:?} is not in the same tree as its root {:?}",
+                v, w
+            ));
         }
+        Ok(())
     }
 
     /*
         Calculate new live states
     */
-    fn calculate_new_live_states(&mut self, v: usize) {
+    fn calculate_new_live_states(&mut self, v: V) {
         // Same fn as in Naive
         if self.is_live(v) {
             for u in
@@ -212,34 +355,124 @@ impl LogStateGraph {
         }
     }
 }
-impl StateGraph for LogStateGraph {
+impl<V: Vertex> LogStateGraph<V> {
+    // Whether remove_transition is supported for transitions from v (see
+    // above): v is not Live, and was not merged into a cycle
+    pub fn can_remove_from(&self, v: V) -> bool {
+        !self.is_seen(v) || !self.is_live(v) && !self.get_node(v).merged
+    }
+}
+
+impl<V: Vertex> Checkpointed<V> for LogStateGraph<V> {
+    fn checkpoints(&mut self) -> &mut Vec<Self> {
+        &mut self.checkpoints
+    }
+    fn events(&mut self) -> &mut StatusEvents<V> {
+        &mut self.events
+    }
+}
+
+impl<V: Vertex> StateGraph<V> for LogStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
-        // println!("# Adding transition: {}, {}", v1, v2);
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
         self.graph.ensure_edge_bck(v1, v2);
-        self.euler_forest.ensure_vertex(v1);
-        self.euler_forest.ensure_vertex(v2);
+        self.euler_forest.ensure_vertex(self.euler_id(v1));
+        self.euler_forest.ensure_vertex(self.euler_id(v2));
         self.calculate_new_live_states(v2);
         if !self.is_live(v1) {
             self.push_reserve(v1, v2);
         }
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
-        // println!("# Marking closed: {}", v);
+    fn mark_closed_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
-        self.euler_forest.ensure_vertex(v);
-        self.check_dead(v);
+        self.euler_forest.ensure_vertex(self.euler_id(v));
+        self.check_dead(&[v]);
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
-        // println!("# Marking live: {}", v);
+    fn mark_closed_batch_unchecked(&mut self, vs: &[V]) {
+        for &v in vs {
+            self.graph.ensure_vertex(v);
+            self.euler_forest.ensure_vertex(self.euler_id(v));
+        }
+        self.check_dead(vs);
+    }
+    fn mark_live_unchecked(&mut self, v: V) {
         self.graph.ensure_vertex(v);
-        // self.euler_forest.ensure_vertex(v);
+        // self.euler_forest.ensure_vertex(self.euler_id(v));
+        self.marked_live.insert(v);
         self.set_status(v, Status::Live);
         self.calculate_new_live_states(v);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn remove_transition_unchecked(&mut self, v1: V, v2: V) {
+        assert!(
+            self.can_remove_from(v1),
+            "LogStateGraph only supports removing transitions from states \
+            that are not Live or merged (tried to remove: {:?} -> {:?})",
+            v1,
+            v2
+        );
+        // Only this transition: v1 may have others into the vertex that v2
+        // was merged into
+        self.graph.remove_edge_bck_orig(v1, v2);
+        self.remove_reserve(v1, v2);
+        if self.get_node(v1).next == Some((v1, v2)) {
+            // Successor is gone: split the forest and search again from v1
+            let (orig_v1, orig_v2) = self.clear_succ(v1);
+            self.euler_forest
+                .remove_edge(self.euler_id(orig_v1), self.euler_id(orig_v2));
+            self.set_status(v1, Status::Open);
+            self.check_dead(&[v1]);
+        }
+    }
+    fn push(&mut self) {
+        self.push_snapshot();
+    }
+    fn pop(&mut self) {
+        self.pop_snapshot();
+    }
+    fn enable_events(&mut self) {
+        self.events.enable(&self.graph, |node| node.status);
+        self.graph.enable_merge_log();
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        self.events.drain(&self.graph, |node| node.status)
+    }
+    fn drain_merges(&mut self) -> Vec<(V, V)> {
+        self.graph.drain_merges()
+    }
+    fn enable_certificates(&mut self) {
+        self.graph.enable_orig_index();
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::dead_certificate(&self.graph, v, |node| node.status)
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::live_certificate(
+            &self.graph,
+            v,
+            |node| node.status,
+            &self.marked_live,
+        )
+    }
+    fn check_invariants(&self) -> Result<(), String> {
+        invariants::check_statuses(&self.graph, |node| node.status)?;
+        for v in self.graph.iter_vertices() {
+            self.check_forest(v)?;
+        }
+        Ok(())
+    }
+    fn to_dot(&self) -> String {
+        // Successor edges of Unknown vertices, between the original states
+        let mut overlay = Overlay::default();
+        for v in self.graph.iter_vertices().filter(|&v| self.is_unknown(v)) {
+            if let Some((orig_v, orig_w)) = self.get_node(v).next {
+                overlay.edges.push((orig_v, orig_w, "next".to_string()));
+            }
+        }
+        dot::to_dot(&self.graph, |node| node.status, &overlay)
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).map(|l| l.status)
     }
     fn get_space(&self) -> usize {
@@ -4,25 +4,59 @@
 
     This just stores the graph using hash tables, and
     does naive DFS to determine whether states are dead.
+
+    Also supports removing transitions, by recalculating all statuses
+    from scratch. For this we keep every transition and closure, including
+    those that are redundant for Live states.
 */
 
+use crate::certificate;
+use crate::checkpoint::Checkpointed;
+use crate::dot::{self, Overlay};
+use crate::events::StatusEvents;
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{StateGraph, Status, StatusEvent, Vertex};
+use crate::invariants;
 use crate::util::FreshClone;
 use std::collections::HashSet;
 
-#[derive(Debug, Default)]
-pub struct NaiveStateGraph {
-    graph: DiGraph<usize, Status>,
+#[derive(Clone, Debug)]
+pub struct NaiveStateGraph<V = usize> {
+    graph: DiGraph<V, Status>,
+    // States that were marked closed / marked live (as opposed to states
+    // that are Live because they can reach a marked live state)
+    closed: HashSet<V>,
+    marked_live: HashSet<V>,
+    // Snapshots for push/pop (see checkpoint.rs)
+    checkpoints: Vec<Self>,
+    // Status change events (see events.rs)
+    events: StatusEvents<V>,
 }
-impl NaiveStateGraph {
-    fn calculate_new_live_states(&mut self, v: usize) {
+impl<V> Default for NaiveStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: Default::default(),
+            closed: Default::default(),
+            marked_live: Default::default(),
+            checkpoints: Default::default(),
+            events: Default::default(),
+        }
+    }
+}
+impl<V: Vertex> NaiveStateGraph<V> {
+    fn set_status(&mut self, v: V, status: Status) {
+        // All status updates go through here so that they are reported
+        self.graph.overwrite_vertex(v, status);
+        self.events.mark(v);
+    }
+    fn calculate_new_live_states(&mut self, v: V) {
         // Mark all states Live backwards from v, but not including v
         if self.is_live(v) {
             for u in
                 self.graph.dfs_bck(v, |u| !self.is_live_bck(u)).fresh_clone()
             {
-                self.graph.overwrite_vertex(u, Status::Live);
+                self.set_status(u, Status::Live);
             }
         }
     }
@@ -34,7 +68,7 @@ impl NaiveStateGraph {
         // uses a DFS, and is worst-case O(m).
 
         // Initialize
-        let (unkdead, openlive): (HashSet<usize>, HashSet<usize>) =
+        let (unkdead, openlive): (HashSet<V>, HashSet<V>) =
             self.graph.iter_vertices().partition(|&v| self.is_u_or_d(v));
         let mut not_dead = HashSet::new();
         for &u in openlive.iter() {
@@ -45,28 +79,103 @@ impl NaiveStateGraph {
         for &v in unkdead.iter() {
             debug_assert!(!(self.is_dead(v) && not_dead.contains(&v)));
             if !not_dead.contains(&v) {
-                self.graph.overwrite_vertex(v, Status::Dead);
+                self.set_status(v, Status::Dead);
             }
         }
     }
+    fn recalculate_all_states(&mut self) {
+        // Recalculate all statuses from scratch. Used after removing a
+        // transition, which can make Live states Unknown or Dead, and
+        // Unknown states Dead.
+        for v in self.graph.iter_vertices().fresh_clone() {
+            let status = if self.marked_live.contains(&v) {
+                Status::Live
+            } else if self.closed.contains(&v) {
+                Status::Unknown
+            } else {
+                Status::Open
+            };
+            self.set_status(v, status);
+        }
+        for v in self.marked_live.iter().copied().fresh_clone() {
+            self.calculate_new_live_states(v);
+        }
+        self.recalculate_dead_states();
+    }
 }
-impl StateGraph for NaiveStateGraph {
+impl<V: Vertex> Checkpointed<V> for NaiveStateGraph<V> {
+    fn checkpoints(&mut self) -> &mut Vec<Self> {
+        &mut self.checkpoints
+    }
+    fn events(&mut self) -> &mut StatusEvents<V> {
+        &mut self.events
+    }
+}
+
+impl<V: Vertex> StateGraph<V> for NaiveStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
         self.graph.ensure_edge(v1, v2);
         self.calculate_new_live_states(v2);
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
-        self.graph.overwrite_vertex(v, Status::Unknown);
+    fn mark_closed_unchecked(&mut self, v: V) {
+        self.closed.insert(v);
+        self.set_status(v, Status::Unknown);
         self.recalculate_dead_states();
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
-        self.graph.overwrite_vertex(v, Status::Live);
+    fn mark_closed_batch_unchecked(&mut self, vs: &[V]) {
+        // Only need to recalculate dead states once
+        for &v in vs {
+            self.closed.insert(v);
+            self.set_status(v, Status::Unknown);
+        }
+        self.recalculate_dead_states();
+    }
+    fn mark_live_unchecked(&mut self, v: V) {
+        self.marked_live.insert(v);
+        self.set_status(v, Status::Live);
         self.calculate_new_live_states(v);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn remove_transition_unchecked(&mut self, v1: V, v2: V) {
+        self.graph.remove_edge(v1, v2);
+        self.recalculate_all_states();
+    }
+    fn push(&mut self) {
+        self.push_snapshot();
+    }
+    fn pop(&mut self) {
+        self.pop_snapshot();
+    }
+    fn enable_events(&mut self) {
+        self.events.enable(&self.graph, |&st| st);
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        self.events.drain(&self.graph, |&st| st)
+    }
+    fn enable_certificates(&mut self) {
+        self.graph.enable_orig_index();
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::dead_certificate(&self.graph, v, |&st| st)
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::live_certificate(
+            &self.graph,
+            v,
+            |&st| st,
+            &self.marked_live,
+        )
+    }
+    fn check_invariants(&self) -> Result<(), String> {
+        invariants::check_statuses(&self.graph, |&st| st)?;
+        invariants::check_closed(&self.graph, |&st| st, &self.closed)
+    }
+    fn to_dot(&self) -> String {
+        dot::to_dot(&self.graph, |&st| st, &Overlay::default())
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).copied()
     }
     fn get_space(&self) -> usize {
@@ -75,4 +184,30 @@ impl StateGraph for NaiveStateGraph {
     fn get_time(&self) -> usize {
         self.graph.get_time()
     }
+
+    // Checked versions also record what is skipped for Live states
+    fn add_transition(&mut self, v1: V, v2: V) {
+        debug_assert!(self.is_open(v1) || self.is_live(v1));
+        if self.is_open(v1) && v1 != v2 {
+            self.add_transition_unchecked(v1, v2);
+        } else if v1 != v2 {
+            self.graph.ensure_edge(v1, v2);
+        }
+    }
+    fn mark_closed(&mut self, v: V) {
+        debug_assert!(self.is_open(v) || self.is_live(v));
+        if self.is_open(v) {
+            self.mark_closed_unchecked(v);
+        } else {
+            self.closed.insert(v);
+        }
+    }
+    fn mark_live(&mut self, v: V) {
+        debug_assert!(self.is_open(v) || self.is_live(v));
+        if self.is_open(v) {
+            self.mark_live_unchecked(v);
+        } else {
+            self.marked_live.insert(v);
+        }
+    }
 }
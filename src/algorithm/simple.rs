@@ -9,31 +9,71 @@
     with one improvement: we used LinkedList instead of HashSet for
     storing edges, because it allows merging edge sets in O(1).
     (see graph.rs)
+
+    Closing a batch of states uses the default (one at a time): merging
+    cycles through all the newly closed states at once would search forward
+    through the whole batch from each state.
+
+    Also supports removing transitions. Since merged vertices can't be split
+    again, this rebuilds the graph from scratch from a record of every
+    transition and closure.
 */
 
+use crate::certificate;
+use crate::checkpoint::Checkpointed;
+use crate::dot::{self, Overlay};
+use crate::events::StatusEvents;
 use crate::graph::DiGraph;
-use crate::interface::{StateGraph, Status};
+use crate::interface::{StateGraph, Status, StatusEvent, Vertex};
+use crate::invariants;
 use crate::util::FreshClone;
 use std::collections::HashSet;
 
-#[derive(Debug, Default)]
-pub struct SimpleStateGraph {
-    graph: DiGraph<usize, Status>,
+#[derive(Clone, Debug)]
+pub struct SimpleStateGraph<V = usize> {
+    graph: DiGraph<V, Status>,
+    // Record of the input, used to rebuild the graph after a removal
+    // (includes transitions and closures that are skipped for Live states)
+    edges: Vec<(V, V)>,
+    closed: HashSet<V>,
+    marked_live: HashSet<V>,
+    // Snapshots for push/pop (see checkpoint.rs)
+    checkpoints: Vec<Self>,
+    // Status change events (see events.rs)
+    events: StatusEvents<V>,
+}
+impl<V> Default for SimpleStateGraph<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            graph: Default::default(),
+            edges: Default::default(),
+            closed: Default::default(),
+            marked_live: Default::default(),
+            checkpoints: Default::default(),
+            events: Default::default(),
+        }
+    }
 }
-impl SimpleStateGraph {
-    fn merge_vertices(&mut self, v1: usize, v2: usize) {
+impl<V: Vertex> SimpleStateGraph<V> {
+    fn set_status(&mut self, v: V, status: Status) {
+        // Same fn as in Naive
+        self.graph.overwrite_vertex(v, status);
+        self.events.mark(v);
+    }
+    fn merge_vertices(&mut self, v1: V, v2: V) {
         // println!("  Merging: {} {}", v1, v2);
         debug_assert!(self.is_u_or_d(v1));
         debug_assert!(self.is_u_or_d(v2));
         debug_assert!(v1 != v2);
         self.graph.merge(v1, v2);
     }
-    fn merge_all_cycles(&mut self, v: usize) {
+    fn merge_all_cycles(&mut self, v: V) {
         // println!("  Merging cycles through: {}", v);
         // Merge all cycles through v
         // (assuming no other cycles in closed states)
         debug_assert!(self.is_u_or_d(v));
-        let fwd_reachable: HashSet<usize> =
+        let fwd_reachable: HashSet<V> =
             self.graph.dfs_fwd(v, |w| self.is_u_or_d(w)).collect();
         for u in
             self.graph.dfs_bck(v, |u| fwd_reachable.contains(&u)).fresh_clone()
@@ -43,7 +83,7 @@ impl SimpleStateGraph {
             self.merge_vertices(u, v);
         }
     }
-    fn check_dead_iterative(&mut self, v: usize) {
+    fn check_dead_iterative(&mut self, v: V) {
         // Check if v is dead and recurse on back edges.
         // println!("  Checking if dead iteratively from: {}", v);
         for u in self
@@ -52,41 +92,119 @@ impl SimpleStateGraph {
             .fresh_clone()
         {
             // println!("  Marking dead: {}", u);
-            self.graph.overwrite_vertex(u, Status::Dead);
+            self.set_status(u, Status::Dead);
         }
     }
-    fn calculate_new_live_states(&mut self, v: usize) {
+    fn calculate_new_live_states(&mut self, v: V) {
         // Same fn as in Naive
         if self.is_live(v) {
             for u in
                 self.graph.dfs_bck(v, |u| !self.is_live_bck(u)).fresh_clone()
             {
-                self.graph.overwrite_vertex(u, Status::Live);
+                self.set_status(u, Status::Live);
             }
         }
     }
+    fn rebuild(&mut self) {
+        // Replay the record on a fresh graph: first all transitions (while
+        // all states are still open), then live states, then closures.
+        let mut fresh = Self::new();
+        if self.graph.has_orig_index() {
+            fresh.graph.enable_orig_index();
+        }
+        for v in self.graph.iter_vertices_all() {
+            fresh.graph.ensure_vertex(v);
+        }
+        for &(v1, v2) in &self.edges {
+            fresh.add_transition_unchecked(v1, v2);
+        }
+        for &v in &self.marked_live {
+            fresh.mark_live(v);
+        }
+        for &v in &self.closed {
+            fresh.mark_closed(v);
+        }
+        self.graph = fresh.graph;
+        self.events.mark_all();
+    }
 }
-impl StateGraph for SimpleStateGraph {
+impl<V: Vertex> Checkpointed<V> for SimpleStateGraph<V> {
+    fn checkpoints(&mut self) -> &mut Vec<Self> {
+        &mut self.checkpoints
+    }
+    fn events(&mut self) -> &mut StatusEvents<V> {
+        &mut self.events
+    }
+}
+
+impl<V: Vertex> StateGraph<V> for SimpleStateGraph<V> {
     fn new() -> Self {
         Default::default()
     }
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+    fn add_transition_unchecked(&mut self, v1: V, v2: V) {
         // println!("Adding transition: {} {}", v1, v2);
+        self.edges.push((v1, v2));
         self.graph.ensure_edge(v1, v2);
         self.calculate_new_live_states(v2);
     }
-    fn mark_closed_unchecked(&mut self, v: usize) {
+    fn mark_closed_unchecked(&mut self, v: V) {
         // println!("Marking closed: {}", v);
-        self.graph.overwrite_vertex(v, Status::Unknown);
+        self.closed.insert(v);
+        self.set_status(v, Status::Unknown);
         self.merge_all_cycles(v);
         self.check_dead_iterative(v);
     }
-    fn mark_live_unchecked(&mut self, v: usize) {
+    fn mark_live_unchecked(&mut self, v: V) {
         // println!("Marking live: {}", v);
-        self.graph.overwrite_vertex(v, Status::Live);
+        self.marked_live.insert(v);
+        self.set_status(v, Status::Live);
         self.calculate_new_live_states(v);
     }
-    fn get_status(&self, v: usize) -> Option<Status> {
+    fn remove_transition_unchecked(&mut self, v1: V, v2: V) {
+        // println!("Removing transition: {} {}", v1, v2);
+        self.edges.retain(|&e| e != (v1, v2));
+        self.rebuild();
+    }
+    fn push(&mut self) {
+        self.push_snapshot();
+    }
+    fn pop(&mut self) {
+        self.pop_snapshot();
+    }
+    fn enable_events(&mut self) {
+        self.events.enable(&self.graph, |&st| st);
+        self.graph.enable_merge_log();
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        self.events.drain(&self.graph, |&st| st)
+    }
+    fn drain_merges(&mut self) -> Vec<(V, V)> {
+        self.graph.drain_merges()
+    }
+    fn enable_certificates(&mut self) {
+        self.graph.enable_orig_index();
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::dead_certificate(&self.graph, v, |&st| st)
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        certificate::live_certificate(
+            &self.graph,
+            v,
+            |&st| st,
+            &self.marked_live,
+        )
+    }
+    fn check_invariants(&self) -> Result<(), String> {
+        invariants::check_statuses(&self.graph, |&st| st)?;
+        invariants::check_closed(&self.graph, |&st| st, &self.closed)?;
+        // All cycles of Unknown states are merged (see merge_all_cycles)
+        invariants::check_acyclic(&self.graph, |v| self.is_unknown(v))
+    }
+    fn to_dot(&self) -> String {
+        dot::to_dot(&self.graph, |&st| st, &Overlay::default())
+    }
+    fn get_status(&self, v: V) -> Option<Status> {
         self.graph.get_label(v).copied()
     }
     fn get_space(&self) -> usize {
@@ -95,4 +213,34 @@ impl StateGraph for SimpleStateGraph {
     fn get_time(&self) -> usize {
         self.graph.get_time()
     }
+
+    // Checked versions also record what is skipped for Live states
+    // (same as in Naive)
+    fn add_transition(&mut self, v1: V, v2: V) {
+        debug_assert!(self.is_open(v1) || self.is_live(v1));
+        if self.is_open(v1) && v1 != v2 {
+            self.add_transition_unchecked(v1, v2);
+        } else if v1 != v2 {
+            // Both states are seen, as in Naive, so that the transition can
+            // be removed later
+            self.graph.ensure_vertex(v2);
+            self.edges.push((v1, v2));
+        }
+    }
+    fn mark_closed(&mut self, v: V) {
+        debug_assert!(self.is_open(v) || self.is_live(v));
+        if self.is_open(v) {
+            self.mark_closed_unchecked(v);
+        } else {
+            self.closed.insert(v);
+        }
+    }
+    fn mark_live(&mut self, v: V) {
+        debug_assert!(self.is_open(v) || self.is_live(v));
+        if self.is_open(v) {
+            self.mark_live_unchecked(v);
+        } else {
+            self.marked_live.insert(v);
+        }
+    }
 }
@@ -67,7 +67,7 @@ impl<V> Default for Node<V> {
     Generic implementation for any "hashy" data structure H --
     this allows different backends other than just HashMap
 */
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct AvlForest<V, H>
 where
     V: Copy + Debug + Eq,
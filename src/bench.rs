@@ -0,0 +1,224 @@
+/*
+    Statistics over repeated runs, for benchmarking
+
+    A single run of an example gives one noisy sample of its time. In
+    benchmarking mode (see driver::run_bench), each algorithm is first run
+    a few times to warm up (the results are discarded), then run N more
+    times, and we report the median, mean, standard deviation, and a 95%
+    confidence interval for the mean, along with the number of outliers.
+
+    Outliers are samples outside Tukey's fences, i.e. more than 1.5 times
+    the interquartile range below the first quartile or above the third.
+    They are counted but not removed: a high count means the machine was
+    noisy, and the median should be preferred over the mean.
+
+    Times are in the same units as the time column of run_compare: in
+    release mode milliseconds, and in debug mode the time counter (see
+    debug_counter.rs), which varies little if at all between runs.
+*/
+
+use super::driver::Algorithm;
+use serde::Serialize;
+use std::fmt;
+
+// Two-sided 95% critical values of Student's t distribution for 1 to 30
+// degrees of freedom; above that, the normal distribution's 1.96 is close
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+const Z_95: f64 = 1.96;
+
+fn t_95(df: usize) -> f64 {
+    T_95.get(df - 1).copied().unwrap_or(Z_95)
+}
+
+// Quantile q (between 0 and 1) of sorted samples, interpolating linearly
+// between the closest two
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/*
+    Statistics of a list of samples
+*/
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SampleStats {
+    pub count: usize,
+    pub median: f64,
+    pub mean: f64,
+    // Sample standard deviation (0 for a single sample)
+    pub stddev: f64,
+    // Half-width of the 95% confidence interval for the mean (infinite for
+    // a single sample)
+    pub ci95: f64,
+    pub min: f64,
+    pub max: f64,
+    pub outliers: usize,
+}
+impl SampleStats {
+    pub fn new(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "No samples");
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let (stddev, ci95) = if count > 1 {
+            let sq_diffs: f64 = sorted.iter().map(|x| (x - mean).powi(2)).sum();
+            let stddev = (sq_diffs / (count - 1) as f64).sqrt();
+            (stddev, t_95(count - 1) * stddev / (count as f64).sqrt())
+        } else {
+            (0.0, f64::INFINITY)
+        };
+        let q1 = quantile(&sorted, 0.25);
+        let q3 = quantile(&sorted, 0.75);
+        let fence = 1.5 * (q3 - q1);
+        let outliers = sorted
+            .iter()
+            .filter(|&&x| x < q1 - fence || x > q3 + fence)
+            .count();
+        Self {
+            count,
+            median: quantile(&sorted, 0.5),
+            mean,
+            stddev,
+            ci95,
+            min: sorted[0],
+            max: sorted[count - 1],
+            outliers,
+        }
+    }
+}
+
+/*
+    Summary for one example and algorithm (None if a run timed out)
+*/
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BenchSummary {
+    pub name: String,
+    pub algorithm: String,
+    pub warmup: usize,
+    pub stats: Option<SampleStats>,
+}
+impl BenchSummary {
+    pub fn new(
+        name: &str,
+        alg: Algorithm,
+        warmup: usize,
+        samples: Option<&[f64]>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            algorithm: alg.to_string(),
+            warmup,
+            stats: samples.map(SampleStats::new),
+        }
+    }
+    pub fn csv_header() -> String {
+        "name, algorithm, warmup, reps, median, mean, stddev, ci95, min, \
+        max, outliers"
+            .to_string()
+    }
+    pub fn csv_row(&self) -> String {
+        let stats = match &self.stats {
+            None => "Timeout".to_string(),
+            Some(s) => format!(
+                "{}, {:.3}, {:.3}, {:.3}, {:.3}, {:.3}, {:.3}, {}",
+                s.count,
+                s.median,
+                s.mean,
+                s.stddev,
+                s.ci95,
+                s.min,
+                s.max,
+                s.outliers
+            ),
+        };
+        format!("{}, {}, {}, {}", self.name, self.algorithm, self.warmup, stats)
+    }
+}
+impl fmt::Display for BenchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match &self.stats {
+            None => return write!(f, "{}: Timeout", self.algorithm),
+            Some(s) => s,
+        };
+        let unit = if cfg!(debug_assertions) { "" } else { "ms" };
+        write!(
+            f,
+            "{}: median {:.3}{}, mean {:.3}{} +- {:.3} (95% CI), \
+            stddev {:.3}, {} runs, {} outliers",
+            self.algorithm,
+            s.median,
+            unit,
+            s.mean,
+            unit,
+            s.ci95,
+            s.stddev,
+            s.count,
+            s.outliers
+        )
+    }
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(x: f64, y: f64) {
+        assert!((x - y).abs() < 1e-9, "{} != {}", x, y);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = SampleStats::new(&[4.0, 2.0, 3.0, 5.0, 1.0]);
+        assert_eq!(stats.count, 5);
+        assert_close(stats.median, 3.0);
+        assert_close(stats.mean, 3.0);
+        assert_close(stats.stddev, 2.5f64.sqrt());
+        assert_close(stats.ci95, 2.776 * 2.5f64.sqrt() / 5f64.sqrt());
+        assert_close(stats.min, 1.0);
+        assert_close(stats.max, 5.0);
+        assert_eq!(stats.outliers, 0);
+    }
+
+    #[test]
+    fn test_single_sample() {
+        let stats = SampleStats::new(&[7.0]);
+        assert_close(stats.median, 7.0);
+        assert_close(stats.stddev, 0.0);
+        assert!(stats.ci95.is_infinite());
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut samples = vec![10.0; 9];
+        samples.extend([10.5, 11.0, 9.5, 50.0]);
+        let stats = SampleStats::new(&samples);
+        assert_close(stats.median, 10.0);
+        assert_eq!(stats.outliers, 4);
+        // Not removed from the mean
+        assert!(stats.mean > 12.0);
+    }
+
+    #[test]
+    fn test_csv() {
+        let alg = Algorithm::Log;
+        let summary = BenchSummary::new("ex", alg, 1, Some(&[1.0, 3.0]));
+        assert_eq!(
+            summary.csv_row(),
+            "ex, log, 1, 2, 2.000, 2.000, 1.414, 12.706, 1.000, 3.000, 0"
+        );
+        let timeout = BenchSummary::new("ex", alg, 1, None);
+        assert_eq!(timeout.csv_row(), "ex, log, 1, Timeout");
+    }
+}
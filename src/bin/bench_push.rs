@@ -0,0 +1,82 @@
+/*
+    Binary to measure the cost of push and pop (see checkpoint.rs).
+
+    Each push saves a full snapshot, so it takes time linear in the size of
+    the graph. This builds a graph from random transactions (see fuzz.rs)
+    with each algorithm, then times a push followed by a pop on the result,
+    and compares that to the time to build the graph from scratch (which is
+    what backtracking would cost without push and pop).
+*/
+
+use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::fuzz::{self, FuzzConfig};
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "push/pop benchmark",
+    about = "Compare the time of a push and pop to the time to build the \
+             graph, for each algorithm."
+)]
+struct Args {
+    #[structopt(long, default_value = "20000", help = "Number of states")]
+    states: usize,
+
+    #[structopt(
+        long,
+        default_value = "100000",
+        help = "Maximum number of transactions"
+    )]
+    len: usize,
+
+    #[structopt(short, long, default_value = "20", help = "Pushes to time")]
+    reps: u32,
+
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+
+    #[structopt(long, help = "Include Naive (slow on large graphs)")]
+    naive: bool,
+}
+impl Args {
+    fn run(&self) {
+        let config =
+            FuzzConfig { states: self.states, len: self.len, remove: false };
+        let transactions = fuzz::random_transactions(self.seed, &config);
+        println!("Transactions: {}", transactions.len());
+        println!("Algorithm, build (ms), push and pop (ms), ratio");
+        let algs = if self.naive {
+            driver::algs_all()
+        } else {
+            driver::algs_excluding(&[Algorithm::Naive])
+        };
+        for alg in algs {
+            let mut graph = alg.new_graph();
+            let start = Instant::now();
+            graph.process_batch(&transactions);
+            let build = start.elapsed();
+            let start = Instant::now();
+            for _ in 0..self.reps {
+                graph.push();
+                graph.pop();
+            }
+            let push = start.elapsed() / self.reps;
+            println!(
+                "{}, {:.3}, {:.3}, {:.4}",
+                alg,
+                millis(build),
+                millis(push),
+                push.as_secs_f64() / build.as_secs_f64()
+            );
+        }
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn main() {
+    Args::from_args().run();
+}
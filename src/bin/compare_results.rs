@@ -0,0 +1,96 @@
+/*
+    Binary to compare a result CSV (from run_all) against a baseline, and
+    list the examples that got slower or faster, newly time out, or whose
+    output became wrong or correct (see regression.rs).
+
+    Exits with status 1 if there are any regressions, so it can be used to
+    check a change to an algorithm, e.g.:
+        compare_results results/baseline.csv results/new.csv
+*/
+
+use guided_digraph::regression;
+use guided_digraph::report::ResultSet;
+use std::process;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "compare results",
+    about = "Compare a result CSV against a baseline; fail on regressions."
+)]
+struct Args {
+    #[structopt(help = "Baseline result CSV")]
+    baseline: String,
+
+    #[structopt(help = "New result CSV")]
+    new: String,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "0.2",
+        help = "Relative change in time to report (0.2: 20% slower or faster)"
+    )]
+    threshold: f64,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "10",
+        help = "Smallest absolute change in time to report (ms)"
+    )]
+    min_diff: f64,
+}
+impl Args {
+    // Returns whether there were any regressions
+    fn run(&self) -> bool {
+        let baseline = ResultSet::load_from(&self.baseline);
+        let new = ResultSet::load_from(&self.new);
+        println!(
+            "Baseline: {} ({} examples)",
+            self.baseline,
+            baseline.rows.len()
+        );
+        println!("New: {} ({} examples)", self.new, new.rows.len());
+        let (only_base, only_new) = regression::unmatched(&baseline, &new);
+        if !only_base.is_empty() || !only_new.is_empty() {
+            println!(
+                "Skipped {} examples only in the baseline and {} only in \
+                the new results",
+                only_base.len(),
+                only_new.len()
+            );
+        }
+
+        let changes =
+            regression::compare(&baseline, &new, self.threshold, self.min_diff);
+        // Changes are sorted by kind, so print them in groups
+        let mut i = 0;
+        while i < changes.len() {
+            let kind = changes[i].kind;
+            let n = changes[i..].iter().take_while(|c| c.kind == kind).count();
+            println!("===== {} ({}) =====", kind, n);
+            for change in &changes[i..(i + n)] {
+                println!("{}", change);
+            }
+            i += n;
+        }
+
+        let regressions = changes.iter().filter(|c| c.is_regression()).count();
+        println!("===== Result =====");
+        println!(
+            "{} changes, {} regressions (threshold {}%, min diff {}ms)",
+            changes.len(),
+            regressions,
+            self.threshold * 100.0,
+            self.min_diff
+        );
+        regressions > 0
+    }
+}
+
+fn main() {
+    if Args::from_args().run() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,82 @@
+/*
+    Binary to convert example inputs between JSON and the compact binary
+    format (see compact.rs).
+
+    Each file is converted to the other format, saved next to the original
+    (e.g. 'examples/handwritten/2_in.json' -> 'examples/handwritten/2_in.bin').
+    When both exist, the compact file is the one that gets loaded.
+*/
+
+use guided_digraph::constants::{
+    ALL_EXAMPLE_DIRS, EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT,
+};
+use guided_digraph::example::ExampleInput;
+use guided_digraph::util;
+use std::fs;
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "convert example input",
+    about = "Convert example inputs between JSON and the compact binary format."
+)]
+struct Args {
+    #[structopt(help = "Input files to convert \
+        (e.g. 'examples/handwritten/2_in.json')")]
+    files: Vec<String>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Convert all JSON inputs in the known example directories"
+    )]
+    all: bool,
+}
+
+fn file_size(path: &str) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn convert(infile: &str) {
+    let outfile = if let Some(base) = infile.strip_suffix(EXAMPLE_IN_EXT) {
+        format!("{}{}", base, EXAMPLE_IN_BIN_EXT)
+    } else if let Some(base) = infile.strip_suffix(EXAMPLE_IN_BIN_EXT) {
+        format!("{}{}", base, EXAMPLE_IN_EXT)
+    } else {
+        panic!(
+            "Input file should end in {} or {}: {}",
+            EXAMPLE_IN_EXT, EXAMPLE_IN_BIN_EXT, infile
+        );
+    };
+    let input = ExampleInput::load_from_file(Path::new(infile));
+    input.save_to_file(Path::new(&outfile));
+    println!(
+        "{} ({} bytes) -> {} ({} bytes)",
+        infile,
+        file_size(infile),
+        outfile,
+        file_size(&outfile)
+    );
+}
+
+impl Args {
+    fn run(&self) {
+        for file in &self.files {
+            convert(file);
+        }
+        if self.all {
+            for dir in ALL_EXAMPLE_DIRS {
+                for path in util::paths_in(dir) {
+                    if path.ends_with(EXAMPLE_IN_EXT) {
+                        convert(&path);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
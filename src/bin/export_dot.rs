@@ -0,0 +1,80 @@
+/*
+    Binary to draw the state graph of an algorithm partway through an
+    example, in Graphviz DOT format (see dot.rs).
+
+    Replays the first --steps transactions of the example (by default, all
+    of them) and writes the result to a .dot file, which can be rendered
+    with e.g.
+        dot -Tsvg results/2_jump_5.dot -o 2.svg
+*/
+
+use guided_digraph::constants::RESULTS_DIR;
+use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::example::Example;
+use guided_digraph::util;
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "export DOT",
+    about = "Replay an example up to a given transaction and write the \
+             state graph as a Graphviz .dot file."
+)]
+struct Args {
+    #[structopt(help = "Path to example without file extension \
+                        (e.g. 'examples/handwritten/2')")]
+    basename: String,
+
+    #[structopt(short, long, default_value = "Jump")]
+    algorithm: Algorithm,
+
+    #[structopt(
+        short = "k",
+        long,
+        help = "Number of transactions to replay (default: all)"
+    )]
+    steps: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Where to write the .dot file (default: \
+                results/<name>_<algorithm>_<steps>.dot)"
+    )]
+    output: Option<String>,
+}
+impl Args {
+    fn run(&self) {
+        let example = Example::load_from(&self.basename);
+        let steps = self.steps.unwrap_or(example.len()).min(example.len());
+        let dot = driver::dot_after(&example, self.algorithm, steps);
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => {
+                let name = Path::new(&self.basename)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_else(|| {
+                        panic!("Not an example path: {}", self.basename)
+                    });
+                format!(
+                    "{}/{}_{}_{}.dot",
+                    RESULTS_DIR, name, self.algorithm, steps
+                )
+            }
+        };
+        util::lines_to_file(&output, vec![dot]);
+        println!(
+            "Replayed {} of {} transactions with {}",
+            steps,
+            example.len(),
+            self.algorithm
+        );
+        println!("Saved to {}", output);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -0,0 +1,124 @@
+/*
+    Binary to fuzz the algorithms against each other on random transaction
+    sequences (see fuzz.rs).
+
+    Sequence number i is generated from the seed --seed + i, so a failure
+    can be reproduced with --iters 1 and its seed. The first failure is
+    shrunk and saved as an example in examples/handwritten (with no
+    expected output, so assert_example compares the algorithms on it), and
+    the binary exits with an error code.
+
+    Log only supports some removals, so it is not run with --remove; use
+    --remove-log to fuzz its removals separately (see fuzz.rs).
+*/
+
+use guided_digraph::constants::{EXAMPLE_IN_EXT, EX_DIR_HANDWRITTEN};
+use guided_digraph::example::{Example, ExampleInput};
+use guided_digraph::fuzz::{self, FuzzConfig};
+use guided_digraph::util;
+use std::panic;
+use std::process;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "differential fuzzing",
+    about = "Check that all algorithms agree after every transaction on \
+             random transaction sequences."
+)]
+struct Args {
+    #[structopt(short = "n", long, default_value = "1000")]
+    iters: u64,
+
+    #[structopt(long, default_value = "0")]
+    seed: u64,
+
+    #[structopt(long, default_value = "10", help = "Number of states")]
+    states: usize,
+
+    #[structopt(
+        long,
+        default_value = "50",
+        help = "Maximum number of transactions in each sequence"
+    )]
+    len: usize,
+
+    #[structopt(
+        short,
+        long,
+        help = "Also generate Remove transactions (only runs the algorithms \
+                that support them)"
+    )]
+    remove: bool,
+
+    #[structopt(
+        long,
+        help = "Generate only the removals that Log supports, and compare \
+                Log against Naive and Simple"
+    )]
+    remove_log: bool,
+
+    #[structopt(long, help = "Don't save the failing example")]
+    no_save: bool,
+}
+impl Args {
+    fn run(&self) {
+        let config = FuzzConfig {
+            states: self.states,
+            len: self.len,
+            remove: self.remove,
+        };
+        let algs = if self.remove_log {
+            fuzz::LOG_REMOVE_ALGS.to_vec()
+        } else {
+            config.algs()
+        };
+        let algs: Vec<String> =
+            algs.iter().map(|alg| alg.to_string()).collect();
+        println!("Algorithms: {}", algs.join(", "));
+        let seeds = self.seed..self.seed + self.iters;
+        // Panics in the algorithms are reported as failures, and shrinking
+        // reruns them many times, so don't print each one
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = if self.remove_log {
+            fuzz::fuzz_log_remove(seeds, &config)
+        } else {
+            fuzz::fuzz(seeds, &config)
+        };
+        panic::set_hook(hook);
+        let failure = match result {
+            None => {
+                println!("All {} sequences passed.", self.iters);
+                return;
+            }
+            Some(failure) => failure,
+        };
+        println!("===== Failure for seed {} =====", failure.seed);
+        println!(
+            "Shrunk from {} to {} transactions:",
+            failure.original.len(),
+            failure.shrunk.len()
+        );
+        for (step, t) in failure.shrunk.iter().enumerate() {
+            println!("{}: {:?}", step, t);
+        }
+        println!("{}", failure.failure);
+        if !self.no_save {
+            let basename =
+                format!("{}/fuzz_{}", EX_DIR_HANDWRITTEN, failure.seed);
+            if util::file_exists(format!("{}{}", basename, EXAMPLE_IN_EXT)) {
+                println!("Not saved: {} already exists", basename);
+            } else {
+                let input = ExampleInput(failure.shrunk);
+                Example::new(&basename, input, None).save();
+                println!("Saved to {}", basename);
+            }
+        }
+        process::exit(1);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
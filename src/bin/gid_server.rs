@@ -0,0 +1,83 @@
+/*
+    Binary to keep a state graph alive and answer commands one line at a
+    time (see server.rs for the protocol), so that tools in other languages
+    can use the algorithms without bindings.
+
+    By default it reads commands from stdin and answers on stdout. With
+    --socket, it listens on a Unix domain socket instead, and serves one
+    connection at a time; the graph is kept between connections.
+*/
+
+use guided_digraph::driver::Algorithm;
+use guided_digraph::server::Server;
+use std::io::{self, BufReader};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "gid server",
+    about = "Keep a state graph alive and answer line-based commands \
+             (add, close, live, remove, push, pop, status, dead-set, stats) \
+             on stdin/stdout or a Unix socket."
+)]
+struct Args {
+    #[structopt(short, long, default_value = "Jump")]
+    algorithm: Algorithm,
+
+    #[structopt(
+        short,
+        long,
+        help = "Listen on a Unix domain socket at this path"
+    )]
+    socket: Option<String>,
+}
+impl Args {
+    fn run(&self) {
+        let mut server = Server::new(self.algorithm);
+        match &self.socket {
+            None => {
+                server
+                    .serve(io::stdin().lock(), &mut io::stdout().lock())
+                    .unwrap_or_else(|err| panic!("{}", err));
+            }
+            Some(path) => serve_socket(&mut server, path),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn serve_socket(server: &mut Server, path: &str) {
+    use std::fs;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixListener;
+
+    // Remove a stale socket left by a previous run (but nothing else)
+    if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+        fs::remove_file(path).unwrap_or_else(|err| {
+            panic!("Could not remove old socket: {} -- {}", path, err)
+        });
+    }
+    let listener = UnixListener::bind(path).unwrap_or_else(|err| {
+        panic!("Could not listen on socket: {} -- {}", path, err)
+    });
+    eprintln!("Listening on {}", path);
+    for stream in listener.incoming() {
+        // A client that disconnects early shouldn't stop the server
+        let result = stream.and_then(|mut stream| {
+            let input = BufReader::new(stream.try_clone()?);
+            server.serve(input, &mut stream)
+        });
+        if let Err(err) = result {
+            eprintln!("Connection error: {}", err);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn serve_socket(_server: &mut Server, _path: &str) {
+    panic!("--socket is only supported on Unix");
+}
+
+fn main() {
+    Args::from_args().run();
+}
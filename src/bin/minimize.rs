@@ -0,0 +1,115 @@
+/*
+    Binary to minimize an example on which two algorithms give different
+    outputs.
+
+    Deletes transactions from the example input (see fuzz::shrink) for as
+    long as the two algorithms still disagree, so that the result is
+    minimal: deleting any single transaction makes them agree. The result
+    is saved as a new example, with no expected output.
+
+    Each step reruns both algorithms, so for large examples it helps to
+    build in release mode. Runs that time out or panic count as agreeing.
+    The timeout is only checked between transactions (see
+    fuzz::outputs_differ).
+*/
+
+use guided_digraph::driver::Algorithm;
+use guided_digraph::example::{Example, ExampleInput};
+use guided_digraph::fuzz;
+use std::panic;
+use std::process;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "minimize example",
+    about = "Minimize an example on which two algorithms give different \
+             outputs."
+)]
+struct Args {
+    #[structopt(help = "Path to example without file extension \
+                        (e.g. 'examples/handwritten/2')")]
+    basename: String,
+
+    #[structopt(help = "First algorithm")]
+    alg1: Algorithm,
+
+    #[structopt(help = "Second algorithm")]
+    alg2: Algorithm,
+
+    #[structopt(
+        short,
+        long,
+        help = "Where to save the minimized example, without file \
+                extension (default: the basename followed by _min)"
+    )]
+    output: Option<String>,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "10",
+        help = "Timeout for each run of an algorithm, in seconds"
+    )]
+    timeout: u64,
+}
+impl Args {
+    fn run(&self) {
+        let example = Example::load_from(&self.basename);
+        let timeout = Duration::from_secs(self.timeout);
+        let differ =
+            |ts: &[_]| fuzz::outputs_differ(ts, self.alg1, self.alg2, timeout);
+        println!("===== {} =====", example.name());
+        println!("Example size: {}", example.len());
+        // Panics are counted as agreeing, so don't print each one
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let transactions = fuzz::drop_redundant(&example.input.0);
+        let minimized = if !differ(&example.input.0) {
+            None
+        } else if !differ(&transactions) {
+            // Possible only with Remove transactions
+            println!("Outputs agree after dropping redundant transactions");
+            None
+        } else {
+            println!(
+                "Dropped {} redundant transactions",
+                example.len() - transactions.len()
+            );
+            Some(fuzz::shrink(&transactions, |ts| {
+                let result = differ(ts);
+                if result {
+                    println!("Reduced to {} transactions", ts.len());
+                }
+                result
+            }))
+        };
+        panic::set_hook(hook);
+        let minimized = minimized.unwrap_or_else(|| {
+            println!("{} and {} give the same output", self.alg1, self.alg2);
+            process::exit(1);
+        });
+        println!("=== Minimized input ===");
+        for (step, t) in minimized.iter().enumerate() {
+            println!("{}: {:?}", step, t);
+        }
+        let minimized = Example::new("", ExampleInput(minimized), None);
+        for alg in [self.alg1, self.alg2] {
+            let result =
+                minimized.run_with_timeout(alg.new_graph().as_mut(), timeout);
+            println!("=== Output ({}) ===", alg);
+            println!("{}", result.output_str());
+        }
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => format!("{}_min", self.basename),
+        };
+        Example::new(&output, minimized.input, None).save();
+        println!("Saved to {}", output);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -0,0 +1,103 @@
+/*
+    Binary to aggregate result CSVs (from run_all) into a report, replacing
+    the spreadsheet step (see report.rs).
+
+    Writes to the output directory:
+    - report.md: Markdown tables of examples solved, mean and median times
+      per category, and mean times and speedups per size bucket;
+    - categories.csv, buckets.csv, cactus.csv: the same data as CSV;
+    - cactus.svg, scatter.svg: plots as in Figure 5.
+    The Markdown report is also printed.
+*/
+
+use guided_digraph::constants::RESULTS_DIR;
+use guided_digraph::report::{Report, ResultSet};
+use guided_digraph::util;
+use std::fs;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "report",
+    about = "Aggregate result CSVs into tables and plots."
+)]
+struct Args {
+    #[structopt(
+        required = true,
+        help = "Result CSV files (e.g. 'results/2023-04-28-212821_release_t10.csv'); \
+                later files override earlier ones for the same example"
+    )]
+    files: Vec<String>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Output directory [default: results/report]"
+    )]
+    output: Option<String>,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "10",
+        help = "Exclude examples where all algorithms take less than this (ms)"
+    )]
+    min_time: f64,
+
+    #[structopt(short, long, default_value = "bfgt")]
+    baseline: String,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "jump",
+        help = "Algorithm whose speedup over the baseline is reported"
+    )]
+    target: String,
+}
+impl Args {
+    fn run(&self) {
+        let mut results = ResultSet::new();
+        for file in &self.files {
+            results.merge(ResultSet::load_from(file));
+        }
+        let report =
+            Report::new(&results, self.min_time, &self.baseline, &self.target);
+        let markdown = report.to_markdown();
+        for line in &markdown {
+            println!("{}", line);
+        }
+
+        let dir = match &self.output {
+            Some(dir) => dir.clone(),
+            None => format!("{}/report", RESULTS_DIR),
+        };
+        fs::create_dir_all(&dir).unwrap_or_else(|err| {
+            panic!("Could not create directory: {} -- {}", dir, err)
+        });
+        util::lines_to_file(format!("{}/report.md", dir), markdown);
+        util::lines_to_file(
+            format!("{}/categories.csv", dir),
+            report.categories_csv(),
+        );
+        util::lines_to_file(
+            format!("{}/buckets.csv", dir),
+            report.buckets_csv(),
+        );
+        util::lines_to_file(format!("{}/cactus.csv", dir), report.cactus_csv());
+        util::lines_to_file(
+            format!("{}/cactus.svg", dir),
+            vec![report.cactus_svg()],
+        );
+        util::lines_to_file(
+            format!("{}/scatter.svg", dir),
+            vec![report.scatter_svg()],
+        );
+        println!();
+        println!("Report saved to: {}/", dir);
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -2,13 +2,34 @@
     Run all state graph algorithms on every known example input.
 
     See constants::ALL_EXAMPLE_DIRS for the list of known examples.
+
+    With --jobs N, examples are run in parallel on N threads. The CSV rows
+    are in the same order as in a serial run, but timings are noisier, so
+    the default is a serial run (--jobs 1), which should be used for the
+    timing results.
+
+    With --reps N, runs in benchmarking mode instead: each algorithm is run
+    N times on each example (after --warmup runs), and statistics of the
+    times (see bench.rs) are saved, one row per example and algorithm.
+    Benchmarking always runs serially.
+
+    The examples can be restricted to a subset, e.g. to iterate on a few
+    examples: by directory (--dir, --exclude-dir), by glob patterns on the
+    path (--name), by number of transactions (--min-size, --max-size), and
+    to a random sample of the rest (--sample, with --seed).
 */
 
+use glob::Pattern;
+use guided_digraph::bench::BenchSummary;
 use guided_digraph::constants::{ALL_EXAMPLE_DIRS, RESULTS_DIR};
-use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::driver::{self, Algorithm, ExampleFilter};
 use guided_digraph::util;
+use std::thread;
 use structopt::StructOpt;
 
+// Track memory in release mode (see memory.rs)
+guided_digraph::track_memory!();
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "run all",
@@ -23,6 +44,69 @@ struct Args {
 
     #[structopt(short, long, default_value = "10")]
     timeout: u64,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "1",
+        help = "Number of examples to run in parallel (0: one per CPU)"
+    )]
+    jobs: usize,
+
+    #[structopt(
+        short,
+        long,
+        help = "Benchmark: run each algorithm this many times per example"
+    )]
+    reps: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "1",
+        help = "Number of runs to discard before the repetitions"
+    )]
+    warmup: usize,
+
+    #[structopt(
+        short,
+        long,
+        help = "Only run examples in these directories (or subdirectories)"
+    )]
+    dir: Vec<String>,
+
+    #[structopt(long, help = "Skip examples in these directories")]
+    exclude_dir: Vec<String>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Only run examples whose path matches one of these glob \
+                patterns (e.g. 'examples/random/sparse_*')"
+    )]
+    name: Vec<Pattern>,
+
+    #[structopt(
+        long,
+        help = "Only run examples with at least this many transactions"
+    )]
+    min_size: Option<usize>,
+
+    #[structopt(
+        long,
+        help = "Only run examples with at most this many transactions"
+    )]
+    max_size: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Only run this many of the selected examples, chosen at random"
+    )]
+    sample: Option<usize>,
+
+    #[structopt(long, default_value = "0", help = "Random seed for --sample")]
+    seed: u64,
 }
 impl Args {
     fn run(&self) {
@@ -34,31 +118,125 @@ impl Args {
         println!("Mode: {}", mode);
         println!("Timeout: {}s", self.timeout);
         println!("Algs: {:?} (excluding {:?})", algs, self.exclude);
+        let selected = self.filter().select(ALL_EXAMPLE_DIRS);
+        let num_examples: usize = selected.iter().map(|(_, b)| b.len()).sum();
+        println!("Examples: {}", num_examples);
+        if let Some(reps) = self.reps {
+            if self.jobs != 1 {
+                println!("Warning: benchmarking runs serially, ignoring jobs");
+            }
+            self.run_bench(&selected, &algs, &datetime, mode, reps);
+            return;
+        }
+        let jobs = self.num_jobs();
+        println!("Jobs: {}", jobs);
+        if jobs > 1 {
+            println!("Warning: timings are less reliable with jobs > 1");
+        }
         let mut result_lines = vec![driver::run_compare_csv_header(&algs)];
-        for dir in ALL_EXAMPLE_DIRS {
-            println!("======= directory: {} =======", dir);
-            for basename in driver::example_basenames_in_dir(dir) {
+        if jobs > 1 && !self.print {
+            // Run all examples in one pool, rather than one directory at a
+            // time, so that threads don't wait at the end of each directory
+            let basenames: Vec<String> = selected
+                .into_iter()
+                .flat_map(|(_, basenames)| basenames)
+                .collect();
+            println!("======= {} examples =======", basenames.len());
+            result_lines.extend(driver::run_compare_all(
+                &basenames,
+                &algs,
+                self.timeout,
+                None,
+                jobs,
+            ));
+        } else {
+            for (dir, basenames) in selected {
+                println!("======= directory: {} =======", dir);
                 if self.print {
-                    println!("{}", basename);
+                    for basename in basenames {
+                        println!("{}", basename);
+                    }
                 } else {
-                    result_lines.push(driver::run_compare(
-                        &basename,
+                    result_lines.extend(driver::run_compare_all(
+                        &basenames,
                         &algs,
                         self.timeout,
+                        None,
+                        1,
                     ));
                 }
             }
         }
         if !self.print {
             println!("========= Results =========");
+            // Parallel runs are marked, since their timings are not
+            // comparable with serial runs
+            let jobs_str =
+                if jobs > 1 { format!("_j{}", jobs) } else { String::new() };
             let filepath = format!(
-                "{}/{}_{}_t{}.csv",
-                RESULTS_DIR, datetime, mode, self.timeout
+                "{}/{}_{}_t{}{}.csv",
+                RESULTS_DIR, datetime, mode, self.timeout, jobs_str
             );
             util::lines_to_file(&filepath, result_lines);
             println!("Results saved to: {}", filepath);
         }
     }
+    fn run_bench(
+        &self,
+        selected: &[(String, Vec<String>)],
+        algs: &[Algorithm],
+        datetime: &str,
+        mode: &str,
+        reps: usize,
+    ) {
+        println!("Warmup runs: {}, repetitions: {}", self.warmup, reps);
+        let mut result_lines = vec![BenchSummary::csv_header()];
+        for (dir, basenames) in selected {
+            println!("======= directory: {} =======", dir);
+            for basename in basenames {
+                if self.print {
+                    println!("{}", basename);
+                    continue;
+                }
+                let summaries = driver::run_bench(
+                    basename,
+                    algs,
+                    self.timeout,
+                    self.warmup,
+                    reps,
+                );
+                result_lines
+                    .extend(summaries.iter().map(BenchSummary::csv_row));
+            }
+        }
+        if !self.print {
+            println!("========= Results =========");
+            let filepath = format!(
+                "{}/{}_{}_t{}_bench_r{}.csv",
+                RESULTS_DIR, datetime, mode, self.timeout, reps
+            );
+            util::lines_to_file(&filepath, result_lines);
+            println!("Results saved to: {}", filepath);
+        }
+    }
+    fn filter(&self) -> ExampleFilter {
+        ExampleFilter {
+            dirs: self.dir.clone(),
+            exclude_dirs: self.exclude_dir.clone(),
+            patterns: self.name.clone(),
+            min_size: self.min_size,
+            max_size: self.max_size,
+            sample: self.sample,
+            seed: self.seed,
+        }
+    }
+    fn num_jobs(&self) -> usize {
+        if self.jobs == 0 {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        } else {
+            self.jobs
+        }
+    }
 }
 
 fn main() {
@@ -1,11 +1,25 @@
 /*
     Binary to run all state graph algorithms on an example input
     and compare stats.
+
+    With --latency, each transaction is timed instead, and the latency of
+    each kind of transaction (p50, p99, max, and mean) is saved to a CSV
+    file, along with a JSON file that also has the full histograms.
+
+    With --reps N, each algorithm is run N times (after --warmup runs), and
+    statistics of the times (see bench.rs) are saved to a CSV file.
 */
 
+use guided_digraph::bench::BenchSummary;
+use guided_digraph::constants::RESULTS_DIR;
 use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::latency::LatencySummary;
+use guided_digraph::util;
 use structopt::StructOpt;
 
+// Track memory in release mode (see memory.rs)
+guided_digraph::track_memory!();
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "run perf/stats comparison",
@@ -21,11 +35,85 @@ struct Args {
 
     #[structopt(short, long, default_value = "10")]
     timeout: u64,
+
+    #[structopt(
+        short,
+        long,
+        help = "Process transactions in batches of this size"
+    )]
+    batch: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        conflicts_with = "batch",
+        help = "Record the latency of each transaction and save it to \
+                results/ (as CSV and JSON)"
+    )]
+    latency: bool,
+
+    #[structopt(
+        short,
+        long,
+        conflicts_with_all = &["batch", "latency"],
+        help = "Benchmark: run each algorithm this many times and save \
+                statistics of the times to results/"
+    )]
+    reps: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        default_value = "1",
+        help = "Number of runs to discard before the repetitions"
+    )]
+    warmup: usize,
 }
 impl Args {
     fn run(&self) {
         let algs = driver::algs_excluding(&self.exclude);
-        driver::run_compare(&self.basename, &algs, self.timeout);
+        if self.latency {
+            self.run_latency(&algs);
+        } else if let Some(reps) = self.reps {
+            self.run_bench(&algs, reps);
+        } else {
+            driver::run_compare(
+                &self.basename,
+                &algs,
+                self.timeout,
+                self.batch,
+            );
+        }
+    }
+    fn run_latency(&self, algs: &[Algorithm]) {
+        let summaries = driver::run_latency(&self.basename, algs, self.timeout);
+        let datetime = util::current_datetime_str();
+        let mode = if cfg!(debug_assertions) { "debug" } else { "release" };
+        let basepath = format!("{}/{}_{}_latency", RESULTS_DIR, datetime, mode);
+        let mut lines = vec![LatencySummary::csv_header()];
+        lines.extend(summaries.iter().map(LatencySummary::csv_row));
+        util::lines_to_file(format!("{}.csv", basepath), lines);
+        util::to_json_file(format!("{}.json", basepath), &summaries);
+        println!("Results saved to: {}.csv and {}.json", basepath, basepath);
+    }
+    fn run_bench(&self, algs: &[Algorithm], reps: usize) {
+        let summaries = driver::run_bench(
+            &self.basename,
+            algs,
+            self.timeout,
+            self.warmup,
+            reps,
+        );
+        let datetime = util::current_datetime_str();
+        let mode = if cfg!(debug_assertions) { "debug" } else { "release" };
+        let filepath = format!(
+            "{}/{}_{}_t{}_bench_r{}.csv",
+            RESULTS_DIR, datetime, mode, self.timeout, reps
+        );
+        let mut lines = vec![BenchSummary::csv_header()];
+        lines.extend(summaries.iter().map(BenchSummary::csv_row));
+        util::lines_to_file(&filepath, lines);
+        println!("Results saved to: {}", filepath);
     }
 }
 
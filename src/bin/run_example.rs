@@ -1,10 +1,19 @@
 /*
     Simplest binary: run a state graph algorithm on an example input
+
+    With --check-invariants, instead checks the invariants of the algorithm
+    after every transaction (see invariants.rs), and reports the first
+    transaction after which one is violated.
 */
 
 use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::example::Example;
+use std::process;
 use structopt::StructOpt;
 
+// Track memory in release mode (see memory.rs)
+guided_digraph::track_memory!();
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "run basic example",
@@ -20,14 +29,44 @@ struct Args {
 
     #[structopt(short, long, default_value = "10")]
     timeout: u64,
+
+    #[structopt(
+        short,
+        long,
+        help = "Check invariants after every transaction (slow, and \
+                ignores the timeout)"
+    )]
+    check_invariants: bool,
 }
 impl Args {
     fn run(self) {
-        driver::run_single_example(
-            &self.basename,
+        if self.check_invariants {
+            self.run_checked();
+        } else {
+            driver::run_single_example(
+                &self.basename,
+                self.algorithm,
+                self.timeout,
+            );
+        }
+    }
+    fn run_checked(&self) {
+        let example = Example::load_from(&self.basename);
+        println!("===== {} =====", example.name());
+        println!(
+            "Checking invariants of '{}' on {} transactions...",
             self.algorithm,
-            self.timeout,
+            example.len()
         );
+        match driver::run_checked(&example, self.algorithm) {
+            Ok(()) => println!("Invariants hold after every transaction."),
+            Err((step, err)) => {
+                let t = example.input.0[step];
+                println!("Invariant violated after step {} ({:?}):", step, t);
+                println!("{}", err);
+                process::exit(1);
+            }
+        }
     }
 }
 
@@ -0,0 +1,60 @@
+/*
+    Binary to run a state graph algorithm on a stream of transactions,
+    from a file or piped into stdin, answering status queries as they
+    arrive. See stream.rs for the input format.
+*/
+
+use guided_digraph::driver::Algorithm;
+use guided_digraph::stream::{self, StreamReader};
+use guided_digraph::util;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::DerefMut;
+use std::time::SystemTime;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "run stream",
+    about = "Run a state graph algorithm on a stream of transactions \
+             and status queries (JSON array or JSON lines)."
+)]
+struct Args {
+    #[structopt(help = "Input file (default: read from stdin)")]
+    file: Option<String>,
+
+    #[structopt(short, long, default_value = "Jump")]
+    algorithm: Algorithm,
+}
+impl Args {
+    fn run(&self) {
+        let input: Box<dyn BufRead> = match &self.file {
+            Some(path) if path != "-" => {
+                Box::new(BufReader::new(File::open(path).unwrap_or_else(
+                    |err| panic!("Could not open file: {} -- {}", path, err),
+                )))
+            }
+            _ => Box::new(io::stdin().lock()),
+        };
+        let mut graph = self.algorithm.new_graph();
+        let start = SystemTime::now();
+        let stats = stream::process_stream(
+            graph.deref_mut(),
+            StreamReader::new(input),
+            &mut io::stdout().lock(),
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+        // Summary goes to stderr, to keep stdout for the query results
+        eprintln!(
+            "{}: {} transactions, {} queries, time {}ms",
+            self.algorithm,
+            stats.transactions,
+            stats.queries,
+            util::time_since(&start).as_millis()
+        );
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
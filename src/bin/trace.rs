@@ -0,0 +1,79 @@
+/*
+    Binary to trace a state graph algorithm on an example, one transaction
+    at a time (see trace.rs).
+
+    For each transaction, prints the states that changed status, the states
+    that were merged, and the debug time and space counters (run in debug
+    mode for these). With --stop-at N, stops after transaction number N;
+    with --watch v (which can be repeated), only prints the transactions
+    that involve v, and the status of v at the end.
+*/
+
+use guided_digraph::driver::Algorithm;
+use guided_digraph::example::Example;
+use guided_digraph::trace::Tracer;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "trace example",
+    about = "Replay an example one transaction at a time, printing what \
+             each transaction changed."
+)]
+struct Args {
+    #[structopt(help = "Path to example without file extension \
+                        (e.g. 'examples/handwritten/2')")]
+    basename: String,
+
+    #[structopt(short, long, default_value = "Naive")]
+    algorithm: Algorithm,
+
+    #[structopt(long, help = "Stop after transaction number N (from 0)")]
+    stop_at: Option<usize>,
+
+    #[structopt(
+        short,
+        long,
+        help = "Only print transactions that involve this state (can be \
+                repeated)"
+    )]
+    watch: Vec<usize>,
+}
+impl Args {
+    fn run(&self) {
+        let example = Example::load_from(&self.basename);
+        println!("===== {} =====", example.name());
+        println!("Tracing algorithm '{}'...", self.algorithm);
+        let mut tracer = Tracer::new(self.algorithm);
+        let steps = match self.stop_at {
+            Some(n) => (n + 1).min(example.len()),
+            None => example.len(),
+        };
+        for &t in example.input.0.iter().take(steps) {
+            let step = tracer.process(t);
+            if self.watch.is_empty()
+                || self.watch.iter().any(|&v| step.involves(v))
+            {
+                println!("{}", step);
+            }
+        }
+        if steps < example.len() {
+            println!(
+                "Stopped after {} of {} transactions",
+                steps,
+                example.len()
+            );
+        }
+        for &v in &self.watch {
+            let status = tracer.graph().get_status(v);
+            match status {
+                Some(status) => println!("Status of {}: {:?}", v, status),
+                None => println!("Status of {}: not seen", v),
+            }
+        }
+    }
+}
+
+fn main() {
+    Args::from_args().run();
+}
@@ -0,0 +1,91 @@
+/*
+    Certificates: evidence for the status of a state
+
+    A helper used by each algorithm to implement dead_certificate and
+    live_certificate in the StateGraph interface.
+
+    - For a Dead state v, the certificate is the set of all states reachable
+      from v (including v). These are all closed, and none are Open or Live.
+    - For a Live state v, the certificate is a path of transitions from v to
+      a state that was marked live.
+
+    Both are reconstructed from the original edges in the graph (see
+    iter_bck_edges_orig_from in graph.rs) rather than the merged vertices, so
+    the result only consists of transitions that were actually added.
+    This relies on every algorithm keeping a bck-edge for each transition
+    from a non-Live state; if no path to a state marked live is found, the
+    live certificate is None.
+
+    Each certificate only searches forward from v, so it takes time linear
+    in the number of states and transitions reachable from v. The index of
+    original edges this uses is only kept once enable_orig_index is called
+    (see enable_certificates in interface.rs).
+*/
+
+use super::graph::DiGraph;
+use super::interface::{Status, Vertex};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+
+pub fn dead_certificate<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    v: V,
+    status: impl Fn(&T) -> Status,
+) -> Option<Vec<V>> {
+    if graph.get_label(v).map(&status) != Some(Status::Dead) {
+        return None;
+    }
+    // DFS forward from v
+    let mut visited = HashSet::new();
+    let mut stack = vec![v];
+    let mut result = Vec::new();
+    visited.insert(v);
+    while let Some(u) = stack.pop() {
+        debug_assert_eq!(graph.get_label(u).map(&status), Some(Status::Dead));
+        result.push(u);
+        for w in graph.iter_bck_edges_orig_from(u) {
+            if visited.insert(w) {
+                stack.push(w);
+            }
+        }
+    }
+    Some(result)
+}
+
+pub fn live_certificate<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    v: V,
+    status: impl Fn(&T) -> Status,
+    marked_live: &HashSet<V>,
+) -> Option<Vec<V>> {
+    let is_live = |u| graph.get_label(u).map(&status) == Some(Status::Live);
+    if !is_live(v) {
+        return None;
+    }
+    // BFS forward from v through Live states, so that the path is shortest
+    let mut parent = HashMap::new();
+    let mut queue = VecDeque::new();
+    parent.insert(v, v);
+    queue.push_back(v);
+    while let Some(u) = queue.pop_front() {
+        if marked_live.contains(&u) {
+            // Walk the path back to v
+            let mut path = vec![u];
+            let mut w = u;
+            while w != v {
+                w = parent[&w];
+                path.push(w);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for w in graph.iter_bck_edges_orig_from(u) {
+            if is_live(w) && !parent.contains_key(&w) {
+                parent.insert(w, u);
+                queue.push_back(w);
+            }
+        }
+    }
+    // Only if the algorithm didn't keep the transitions it needs
+    None
+}
@@ -0,0 +1,234 @@
+/*
+    Compact binary encoding of a list of transactions
+
+    An alternative to JSON for example inputs (see example.rs), which for
+    inputs with millions of transactions is much smaller and faster to load.
+
+    Format:
+    - Header: the magic bytes "GIDT", a version byte (currently 1), and the
+      number of transactions.
+    - Then one record per transaction: each vertex is stored as the
+      difference from the previous vertex in the file (zigzag encoded, since
+      it may be negative). The first number of each record also holds the
+      kind of transaction in its lowest 2 bits.
+    All numbers are unsigned LEB128 varints, so a record for a transaction
+    between nearby states usually takes 2 to 3 bytes.
+*/
+
+use super::interface::Transaction;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"GIDT";
+const VERSION: u8 = 1;
+
+const TAG_ADD: u64 = 0;
+const TAG_CLOSE: u64 = 1;
+const TAG_LIVE: u64 = 2;
+const TAG_REMOVE: u64 = 3;
+
+/*
+    Varints
+*/
+
+fn write_varint<W: Write>(w: &mut W, mut x: u64) -> io::Result<()> {
+    while x >= 0x80 {
+        w.write_all(&[(x as u8) | 0x80])?;
+        x >>= 7;
+    }
+    w.write_all(&[x as u8])
+}
+
+fn read_varint<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        if shift >= 64 {
+            return Err(invalid("varint is too long"));
+        }
+        result |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+/*
+    Vertices, delta-encoded
+*/
+
+// Zigzag encoding of the (wrapping) difference v - prev
+fn encode_delta(prev: usize, v: usize) -> u64 {
+    let d = (v as u64).wrapping_sub(prev as u64) as i64;
+    ((d << 1) ^ (d >> 63)) as u64
+}
+
+fn decode_delta(prev: usize, z: u64) -> usize {
+    let d = ((z >> 1) as i64) ^ -((z & 1) as i64);
+    (prev as u64).wrapping_add(d as u64) as usize
+}
+
+/*
+    Exposed API
+*/
+
+pub fn write_transactions<W: Write>(
+    w: &mut W,
+    transactions: &[Transaction],
+) -> io::Result<()> {
+    w.write_all(MAGIC)?;
+    w.write_all(&[VERSION])?;
+    write_varint(w, transactions.len() as u64)?;
+    let mut prev = 0;
+    for &t in transactions {
+        let (tag, v1, v2) = match t {
+            Transaction::Add(v1, v2) => (TAG_ADD, v1, Some(v2)),
+            Transaction::Close(v1) => (TAG_CLOSE, v1, None),
+            Transaction::Live(v1) => (TAG_LIVE, v1, None),
+            Transaction::Remove(v1, v2) => (TAG_REMOVE, v1, Some(v2)),
+        };
+        let z1 = encode_delta(prev, v1);
+        if z1 >> 62 != 0 {
+            // Doesn't leave room for the tag (would need a delta of 2^61)
+            return Err(invalid("vertex too far from previous vertex"));
+        }
+        write_varint(w, (z1 << 2) | tag)?;
+        prev = v1;
+        if let Some(v2) = v2 {
+            write_varint(w, encode_delta(prev, v2))?;
+            prev = v2;
+        }
+    }
+    Ok(())
+}
+
+pub fn read_transactions<R: Read>(r: &mut R) -> io::Result<Vec<Transaction>> {
+    let mut header = [0; 5];
+    r.read_exact(&mut header)?;
+    if &header[0..4] != MAGIC {
+        return Err(invalid("not a compact transaction file (bad header)"));
+    }
+    if header[4] != VERSION {
+        return Err(invalid("unsupported compact transaction file version"));
+    }
+    let len = read_varint(r)? as usize;
+    // Don't trust len for the allocation, in case the file is corrupted
+    let mut result = Vec::with_capacity(len.min(1 << 20));
+    let mut prev = 0;
+    for _ in 0..len {
+        let x = read_varint(r)?;
+        let v1 = decode_delta(prev, x >> 2);
+        prev = v1;
+        let mut next_vertex = || -> io::Result<usize> {
+            prev = decode_delta(prev, read_varint(r)?);
+            Ok(prev)
+        };
+        result.push(match x & 0b11 {
+            TAG_ADD => Transaction::Add(v1, next_vertex()?),
+            TAG_CLOSE => Transaction::Close(v1),
+            TAG_LIVE => Transaction::Live(v1),
+            TAG_REMOVE => Transaction::Remove(v1, next_vertex()?),
+            _ => unreachable!(),
+        });
+    }
+    Ok(result)
+}
+
+// File versions, which panic on errors (like util::from_json_file)
+pub fn from_compact_file<P>(path: P) -> Vec<Transaction>
+where
+    P: AsRef<Path> + Debug,
+{
+    File::open(&path)
+        .and_then(|file| read_transactions(&mut BufReader::new(file)))
+        .unwrap_or_else(|err| {
+            panic!("Could not read transactions from {:?} -- {}", path, err)
+        })
+}
+
+pub fn to_compact_file<P>(path: P, transactions: &[Transaction])
+where
+    P: AsRef<Path> + Debug,
+{
+    File::create(&path)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            write_transactions(&mut writer, transactions)?;
+            writer.flush()
+        })
+        .unwrap_or_else(|err| {
+            panic!("Could not write transactions to {:?} -- {}", path, err)
+        });
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(transactions: &[Transaction]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_transactions(&mut bytes, transactions).unwrap();
+        let result = read_transactions(&mut bytes.as_slice()).unwrap();
+        assert_eq!(result, transactions);
+        bytes
+    }
+
+    #[test]
+    fn test_varint() {
+        for x in [0, 1, 127, 128, 300, 1 << 35, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, x).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_delta() {
+        for (prev, v) in [(0, 0), (0, 5), (5, 0), (3, usize::MAX), (7, 1000)] {
+            assert_eq!(decode_delta(prev, encode_delta(prev, v)), v);
+        }
+        assert_eq!(encode_delta(10, 11), 2);
+        assert_eq!(encode_delta(11, 10), 1);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&[]);
+        let bytes = round_trip(&[
+            Transaction::Add(0, 1),
+            Transaction::Add(1, 2),
+            Transaction::Close(1),
+            Transaction::Live(2),
+            Transaction::Remove(1, 2),
+        ]);
+        // Header, then 8 numbers that each fit in one byte
+        assert_eq!(bytes.len(), 6 + 8);
+        round_trip(&[
+            Transaction::Add(1 << 40, 0),
+            Transaction::Close(usize::MAX),
+            Transaction::Add(0, usize::MAX),
+        ]);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let mut bytes = Vec::new();
+        write_transactions(&mut bytes, &[Transaction::Close(3)]).unwrap();
+        assert!(read_transactions(&mut &bytes[1..]).is_err());
+        assert!(read_transactions(&mut &bytes[..bytes.len() - 1]).is_err());
+        bytes[4] = 2;
+        assert!(read_transactions(&mut bytes.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+/*
+    Thread-safe front end for any implementation of the state graph
+    interface, for sharing one graph between threads.
+
+    The algorithms themselves are not Sync (Jump uses RefCell for its jump
+    lists, and DebugCounter uses Cell), and making them so would slow down
+    the single-threaded case. Instead, the wrapped graph is only accessed by
+    one writer at a time, behind a Mutex, and readers query a separate table
+    of statuses behind a RwLock. After each batch of transactions the writer
+    publishes the status changes to the table, using status change events
+    (see events.rs).
+
+    So get_status never waits for the algorithm to finish a batch: it returns
+    the status as of the last completed batch. Many readers can query at
+    once; they only wait while a batch is being published, which takes time
+    proportional to the number of status changes.
+
+    One difference from get_status on the wrapped graph: states that have
+    been seen stay seen (as Open), even if they disappear after a pop.
+*/
+
+use super::interface::{StateGraph, Status, Transaction, Vertex};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Mutex, RwLock};
+
+pub struct ConcurrentStateGraph<G, V = usize> {
+    graph: Mutex<G>,
+    statuses: RwLock<HashMap<V, Status>>,
+    // G only determines V through the trait, so V is fixed here
+    vertex_type: PhantomData<V>,
+}
+impl<V: Vertex, G: StateGraph<V>> Default for ConcurrentStateGraph<G, V> {
+    fn default() -> Self {
+        let mut graph = G::new();
+        graph.enable_events();
+        Self {
+            graph: Mutex::new(graph),
+            statuses: Default::default(),
+            vertex_type: PhantomData,
+        }
+    }
+}
+impl<V: Vertex, G: StateGraph<V>> ConcurrentStateGraph<G, V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn into_inner(self) -> G {
+        self.graph.into_inner().unwrap()
+    }
+
+    /*
+        Writer API
+    */
+    pub fn process(&self, t: Transaction<V>) {
+        self.process_batch(&[t]);
+    }
+    pub fn process_batch(&self, batch: &[Transaction<V>]) {
+        // Process a whole batch, then publish the status changes at once
+        let mut graph = self.graph.lock().unwrap();
+        graph.process_batch(batch);
+        let mut seen = Vec::new();
+        for &t in batch {
+            match t {
+                Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
+                    seen.push(v1);
+                    seen.push(v2);
+                }
+                Transaction::Close(v) | Transaction::Live(v) => seen.push(v),
+            }
+        }
+        self.publish(&mut *graph, seen);
+    }
+    pub fn with_graph<R>(&self, f: impl FnOnce(&mut G) -> R) -> R {
+        // Run any other operation on the graph (e.g. push/pop, or
+        // certificates), then publish the status changes
+        let mut graph = self.graph.lock().unwrap();
+        let result = f(&mut graph);
+        self.publish(&mut *graph, Vec::new());
+        result
+    }
+
+    /*
+        Reader API
+    */
+    pub fn get_status(&self, v: V) -> Option<Status> {
+        self.statuses.read().unwrap().get(&v).copied()
+    }
+    pub fn is_seen(&self, v: V) -> bool {
+        self.get_status(v).is_some()
+    }
+    pub fn is_live(&self, v: V) -> bool {
+        self.get_status(v) == Some(Status::Live)
+    }
+    pub fn is_dead(&self, v: V) -> bool {
+        self.get_status(v) == Some(Status::Dead)
+    }
+    pub fn is_unknown(&self, v: V) -> bool {
+        self.get_status(v) == Some(Status::Unknown)
+    }
+    pub fn is_open(&self, v: V) -> bool {
+        self.get_status(v).is_none_or(|st| st == Status::Open)
+    }
+
+    /*
+        Internal
+    */
+    fn publish(&self, graph: &mut G, seen: Vec<V>) {
+        // Drain events before taking the lock, to keep readers waiting
+        // as little as possible
+        let events = graph.drain_events();
+        if seen.is_empty() && events.is_empty() {
+            return;
+        }
+        // The checked functions skip some transactions (e.g. an Add from a
+        // Live state), so only publish the states the graph actually saw
+        let seen: Vec<(V, Status)> = seen
+            .into_iter()
+            .filter_map(|v| Some((v, graph.get_status(v)?)))
+            .collect();
+        let mut statuses = self.statuses.write().unwrap();
+        for (v, st) in seen {
+            statuses.entry(v).or_insert(st);
+        }
+        for e in events {
+            statuses.insert(e.vertex, e.new);
+        }
+    }
+}
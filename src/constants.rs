@@ -6,7 +6,9 @@
 */
 
 // File extensions
+// (inputs can be either JSON or the compact binary format in compact.rs)
 pub const EXAMPLE_IN_EXT: &str = "_in.json";
+pub const EXAMPLE_IN_BIN_EXT: &str = "_in.bin";
 pub const EXAMPLE_EXPECT_EXT: &str = "_expect.json";
 
 // Output directory used by run_all
@@ -16,7 +16,7 @@
 #[cfg(debug_assertions)]
 use std::cell::Cell;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct DebugCounter {
     #[cfg(debug_assertions)]
     val: Cell<usize>,
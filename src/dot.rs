@@ -0,0 +1,164 @@
+/*
+    DOT export: drawing the current state graph with Graphviz
+
+    A helper used by each algorithm to implement to_dot in the StateGraph
+    interface. The result can be rendered with e.g.
+        dot -Tsvg graph.dot -o graph.svg
+
+    - Each original state is a node, filled with the color of its status.
+    - States that were merged into a single vertex (by the union-find in
+      graph.rs) are drawn together in a cluster.
+    - Each transition v1 -> v2 that has a bck-edge is drawn as a solid edge
+      between the original states (as in certificate.rs). Transitions out of
+      Live states may be missing, as some algorithms don't keep them.
+    - On top of this, each algorithm can add an Overlay of its own structure:
+      extra text in the node labels, and extra (dashed) edges.
+
+    The output is deterministic: nodes and edges are sorted by the order in
+    which the states were first seen.
+*/
+
+use super::graph::DiGraph;
+use super::interface::{Status, Vertex};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+fn status_color(st: Status) -> &'static str {
+    match st {
+        Status::Live => "palegreen",
+        Status::Dead => "lightcoral",
+        Status::Unknown => "lightgoldenrod",
+        Status::Open => "white",
+    }
+}
+
+// Node names are the Debug output of the state, so escape it
+fn quote<V: Vertex>(v: V) -> String {
+    format!("\"{}\"", format!("{:?}", v).replace('"', "\\\""))
+}
+
+/*
+    Algorithm-specific structure to draw on top of the graph
+*/
+#[derive(Debug)]
+pub struct Overlay<V> {
+    // Extra line of text in the label of a state
+    pub labels: HashMap<V, String>,
+    // Extra edges v1 -> v2, each drawn dashed with the given label
+    pub edges: Vec<(V, V, String)>,
+}
+impl<V> Default for Overlay<V> {
+    fn default() -> Self {
+        Self { labels: HashMap::new(), edges: Vec::new() }
+    }
+}
+
+pub fn to_dot<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    status: impl Fn(&T) -> Status,
+    overlay: &Overlay<V>,
+) -> String {
+    let order = |v: V| graph.get_unique_id(v).unwrap();
+    let mut lines = vec!["digraph {".to_string()];
+    lines.push("    node [style=filled];".to_string());
+
+    // Nodes, grouped by the vertex they were merged into
+    let mut vertices: Vec<V> = graph.iter_vertices().collect();
+    vertices.sort_by_key(|&v| order(v));
+    for (i, &v) in vertices.iter().enumerate() {
+        let color = status_color(graph.get_label(v).map(&status).unwrap());
+        let mut members: Vec<V> = graph.iter_merged(v).collect();
+        members.sort_by_key(|&u| order(u));
+        let cluster = members.len() > 1;
+        if cluster {
+            lines.push(format!("    subgraph cluster_{} {{", i));
+            lines.push("        style=rounded;".to_string());
+        }
+        let indent = if cluster { "        " } else { "    " };
+        for u in members {
+            let mut label = format!("{:?}", u);
+            if let Some(extra) = overlay.labels.get(&u) {
+                label = format!("{}\\n{}", label, extra);
+            }
+            lines.push(format!(
+                "{}{} [label=\"{}\", fillcolor={}];",
+                indent,
+                quote(u),
+                label.replace('"', "\\\""),
+                color
+            ));
+        }
+        if cluster {
+            lines.push("    }".to_string());
+        }
+    }
+
+    // Transitions, without duplicates
+    let mut edges: Vec<(V, V)> = graph
+        .iter_bck_edges_orig()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    edges.sort_by_key(|&(v1, v2)| (order(v1), order(v2)));
+    for (v1, v2) in edges {
+        lines.push(format!("    {} -> {};", quote(v1), quote(v2)));
+    }
+
+    // Overlay edges, sorted by source only, so that edges from the same
+    // state stay in the order given (e.g. jump lists)
+    let mut extra: Vec<&(V, V, String)> = overlay.edges.iter().collect();
+    extra.sort_by_key(|(v1, _, _)| order(*v1));
+    for (v1, v2, label) in extra {
+        lines.push(format!(
+            "    {} -> {} [style=dashed, color=blue, fontcolor=blue, \
+             label=\"{}\"];",
+            quote(*v1),
+            quote(*v2),
+            label.replace('"', "\\\"")
+        ));
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph: DiGraph<usize, Status> = DiGraph::new();
+        graph.ensure_edge(0, 1);
+        graph.ensure_edge(1, 0);
+        graph.ensure_edge(1, 2);
+        graph.ensure_edge(1, 2);
+        graph.merge(0, 1);
+        graph.overwrite_vertex(0, Status::Unknown);
+        graph.overwrite_vertex(2, Status::Live);
+        let mut overlay = Overlay::default();
+        overlay.labels.insert(2, "level 1".to_string());
+        overlay.edges.push((0, 2, "next".to_string()));
+        let dot = to_dot(&graph, |&st| st, &overlay);
+        let expected = [
+            "digraph {",
+            "    node [style=filled];",
+            "    subgraph cluster_0 {",
+            "        style=rounded;",
+            "        \"0\" [label=\"0\", fillcolor=lightgoldenrod];",
+            "        \"1\" [label=\"1\", fillcolor=lightgoldenrod];",
+            "    }",
+            "    \"2\" [label=\"2\\nlevel 1\", fillcolor=palegreen];",
+            "    \"0\" -> \"1\";",
+            "    \"1\" -> \"0\";",
+            "    \"1\" -> \"2\";",
+            "    \"0\" -> \"2\" [style=dashed, color=blue, fontcolor=blue, \
+             label=\"next\"];",
+            "}",
+        ];
+        assert_eq!(dot, expected.join("\n"));
+    }
+}
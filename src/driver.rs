@@ -8,15 +8,35 @@ use super::algorithm::{
     BFGTStateGraph, JumpStateGraph, LogStateGraph, NaiveStateGraph,
     SimpleStateGraph,
 };
-use super::constants::EXAMPLE_IN_EXT;
-use super::example::{Example, ExampleOutput, ExampleResult};
-use super::interface::StateGraph;
+use super::bench::BenchSummary;
+use super::constants::{EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT};
+use super::example::{Example, ExampleOutput, ExampleResult, Progress};
+use super::interface::{StateGraph, Vertex};
+use super::latency::LatencySummary;
+use super::memory;
 use super::util;
+use glob::Pattern;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashSet;
 use std::fmt::{self, Debug};
 use std::ops::DerefMut;
+use std::panic;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
+// Stack size for worker threads (run_preemptive and run_compare_all)
+const WORKER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// Workers abandoned by run_preemptive that may still be running, and how
+// many of them are allowed before a new run waits for the oldest
+static ABANDONED: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+const MAX_ABANDONED: usize = 4;
+
 /*
     Exposed enum for which state graph implementation to use
 */
@@ -55,7 +75,16 @@ impl fmt::Display for Algorithm {
     }
 }
 impl Algorithm {
-    fn new_graph(&self) -> Box<dyn StateGraph> {
+    // Whether the algorithm supports remove_transition in general (Log
+    // only supports some removals, see log.rs)
+    pub fn supports_remove(&self) -> bool {
+        matches!(self, Algorithm::Naive | Algorithm::Simple)
+    }
+    pub fn new_graph(&self) -> Box<dyn StateGraph> {
+        self.new_graph_of()
+    }
+    // As new_graph, with states of any type
+    pub fn new_graph_of<V: Vertex + 'static>(&self) -> Box<dyn StateGraph<V>> {
         match self {
             Algorithm::Naive => Box::new(NaiveStateGraph::new()),
             Algorithm::Simple => Box::new(SimpleStateGraph::new()),
@@ -70,13 +99,25 @@ impl Algorithm {
     Run examples with a given algorithm
 */
 
+// Options for run_core; the default processes transactions one at a time,
+// without timing each one, and prints nothing
+#[derive(Clone, Copy, Debug, Default)]
+struct RunOptions {
+    // Process transactions in batches (see StateGraph::process_batch)
+    batch_size: Option<usize>,
+    // Time each transaction (see latency.rs)
+    latency: bool,
+    // Print the output and whether it is correct
+    verbose: bool,
+}
+
 fn run_core(
-    example: &Example,
+    example: &Arc<Example>,
     alg: Algorithm,
     timeout: Duration,
-    verbose: bool,
+    opts: RunOptions,
 ) -> ExampleResult {
-    if verbose {
+    if opts.verbose {
         println!("===== {} =====", example.name());
         println!(
             "Running algorithm '{}' with timeout {}s...",
@@ -84,38 +125,165 @@ fn run_core(
             timeout.as_secs()
         );
     }
-    let mut graph = alg.new_graph();
-    let result = example.run_with_timeout(graph.deref_mut(), timeout);
+    let result =
+        run_preemptive(example, alg, timeout, opts.batch_size, opts.latency);
 
-    if verbose {
+    if opts.verbose {
         println!("=== Output ===");
         println!("{}", result.output_str());
         println!("=== Result ===");
         println!("Stastics: {}", result.summary());
-        if example.expected.is_some() {
+        if let Some(expected) = &example.expected {
             if result.is_correct() {
                 println!("Output is correct.");
             } else {
                 println!("Output is incorrect!");
                 println!("=== Expected Output ===");
-                println!("{:?}", example.expected.as_ref().unwrap());
+                println!("{:?}", expected);
             }
         }
-    } else {
-        println!("{}: {}", alg, result.summary());
     }
 
     result
 }
 
+// Run the example on a new graph in a separate thread, and stop waiting for
+// it when the timeout expires, even in the middle of a transaction (e.g. a
+// single Close that takes much longer than the timeout).
+// The thread can't be killed, so it is abandoned instead: it is cancelled,
+// and exits (dropping the graph) once its current transaction finishes.
+// Until then it keeps using a CPU and memory, so timings of later runs may
+// be less reliable. Later runs don't wait for it, unless MAX_ABANDONED
+// workers are still running, in which case the next run first waits for
+// the oldest one to exit (see abandoned_workers).
+// If all transactions completed in time, waits for the output to be
+// collected rather than reporting a timeout.
+// If latency, also times each transaction (see latency.rs).
+pub fn run_preemptive(
+    example: &Arc<Example>,
+    alg: Algorithm,
+    timeout: Duration,
+    batch_size: Option<usize>,
+    latency: bool,
+) -> ExampleResult {
+    limit_abandoned();
+    let progress = Arc::new(Progress::default());
+    let (sender, receiver) = mpsc::channel();
+    let worker = {
+        let example = Arc::clone(example);
+        let progress = Arc::clone(&progress);
+        thread::Builder::new()
+            .name(format!("{} {}", alg, example.name()))
+            .stack_size(WORKER_STACK_SIZE)
+            .spawn(move || {
+                let mut graph = alg.new_graph();
+                let result = example.run_with_progress(
+                    graph.deref_mut(),
+                    timeout,
+                    batch_size,
+                    latency,
+                    &progress,
+                );
+                // Fails only if the run was abandoned
+                let _ = sender.send(result);
+            })
+            .unwrap_or_else(|err| {
+                panic!("Could not start worker thread: {}", err)
+            })
+    };
+    let result = match receiver.recv_timeout(timeout) {
+        Ok(result) => Some(result),
+        Err(RecvTimeoutError::Timeout)
+            if progress.completed() == example.len() =>
+        {
+            receiver.recv().ok()
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            progress.cancel();
+            ABANDONED.lock().unwrap().push(worker);
+            return ExampleResult::Timeout(progress.completed());
+        }
+        Err(RecvTimeoutError::Disconnected) => None,
+    };
+    // Wait for the graph to be dropped, so that runs don't overlap; if the
+    // worker panicked (so there is no result), panic here too
+    if let Err(err) = worker.join() {
+        panic::resume_unwind(err);
+    }
+    result.expect("worker thread exited without a result")
+}
+
+// The number of workers abandoned by run_preemptive that are still running
+pub fn abandoned_workers() -> usize {
+    let mut abandoned = ABANDONED.lock().unwrap();
+    join_finished(&mut abandoned);
+    abandoned.len()
+}
+fn join_finished(workers: &mut Vec<JoinHandle<()>>) {
+    let (finished, running): (Vec<_>, Vec<_>) =
+        workers.drain(..).partition(|worker| worker.is_finished());
+    *workers = running;
+    for worker in finished {
+        // Ignore panics: its run was already reported as a timeout
+        let _ = worker.join();
+    }
+}
+fn limit_abandoned() {
+    loop {
+        let oldest = {
+            let mut abandoned = ABANDONED.lock().unwrap();
+            join_finished(&mut abandoned);
+            if abandoned.len() < MAX_ABANDONED {
+                return;
+            }
+            abandoned.remove(0)
+        };
+        // Wait without holding the lock, so other runs can still start
+        let _ = oldest.join();
+    }
+}
+
 pub fn run_single_example(
     basename: &str,
     algorithm: Algorithm,
     timeout_secs: u64,
 ) {
-    let example = Example::load_from(basename);
+    let example = Arc::new(Example::load_from(basename));
     let timeout = Duration::from_secs(timeout_secs);
-    run_core(&example, algorithm, timeout, true);
+    run_core(
+        &example,
+        algorithm,
+        timeout,
+        RunOptions { verbose: true, ..Default::default() },
+    );
+}
+
+// Run the example one transaction at a time on a new graph, checking the
+// invariants of the algorithm after each one (see invariants.rs). On a
+// violation, returns the number of the transaction after which it happened,
+// with a description.
+// There is no timeout, and checking is slow (linear time or worse per
+// transaction), so this is for debugging on small to medium examples.
+pub fn run_checked(
+    example: &Example,
+    alg: Algorithm,
+) -> Result<(), (usize, String)> {
+    let mut graph = alg.new_graph();
+    for (step, &t) in example.input.0.iter().enumerate() {
+        graph.process(t);
+        graph.check_invariants().map_err(|err| (step, err))?;
+    }
+    Ok(())
+}
+
+// Replay the first steps transactions of an example (all of them, if there
+// are fewer) and draw the resulting state graph in DOT format (see dot.rs).
+pub fn dot_after(example: &Example, alg: Algorithm, steps: usize) -> String {
+    let mut graph = alg.new_graph();
+    for &t in example.input.0.iter().take(steps) {
+        graph.process(t);
+    }
+    graph.to_dot()
 }
 
 /*
@@ -150,26 +318,27 @@ pub fn unwrap_timeout(res: &ExampleResult) -> &ExampleOutput {
 }
 
 pub fn assert_example(basename: &str, timeout_secs: Option<u64>) {
-    let example = Example::load_from(basename);
+    let example = Arc::new(Example::load_from(basename));
     let timeout_secs = timeout_secs.unwrap_or(u64::MAX);
     let timeout = Duration::from_secs(timeout_secs);
     let algs = algs_all();
+    let opts = RunOptions { verbose: true, ..Default::default() };
 
     // If example has expected output, check each algorithm is correct
     // separately. Otherwise, compare them with respect to each other.
     if example.expected.is_some() {
         println!("Asserting each algorithm output matches expected...");
         for alg in algs {
-            let out = run_core(&example, alg, timeout, true);
+            let out = run_core(&example, alg, timeout, opts);
             assert!(out.is_correct());
         }
     } else {
         assert!(!algs.is_empty());
         println!("Asserting each algorithm output matches {}...", algs[0]);
-        let out = run_core(&example, algs[0], timeout, true);
+        let out = run_core(&example, algs[0], timeout, opts);
         let expected = unwrap_timeout(&out);
         for &alg in algs.iter().skip(1) {
-            let out = run_core(&example, alg, timeout, true);
+            let out = run_core(&example, alg, timeout, opts);
             assert_eq!(expected, unwrap_timeout(&out));
         }
     }
@@ -179,11 +348,17 @@ pub fn assert_example(basename: &str, timeout_secs: Option<u64>) {
     Performance comparison
 */
 
+// Whether results include space: always in debug mode, and in release mode
+// only if memory is tracked (see memory.rs)
+fn has_space_column() -> bool {
+    cfg!(debug_assertions) || memory::is_tracking()
+}
+
 pub fn run_compare_csv_header(algs: &[Algorithm]) -> String {
     let mut header = "name, size, timeout".to_string();
     for alg in algs {
         header += &format!(", time ({})", alg);
-        if cfg!(debug_assertions) {
+        if has_space_column() {
             header += &format!(", space ({})", alg);
         }
     }
@@ -193,20 +368,49 @@ pub fn run_compare(
     basename: &str,
     algs: &[Algorithm],
     timeout_secs: u64,
+    batch_size: Option<usize>,
 ) -> String {
     // Returns results in CSV format
+    // If batch_size is given, transactions are processed in batches
+    // (see StateGraph::process_batch)
+    run_compare_core(basename, algs, timeout_secs, batch_size, true).0
+}
 
-    let example = Example::load_from(basename);
-    println!("===== {} =====", example.name());
-    println!("Example size: {}, timeout: {}s", example.len(), timeout_secs);
+// Returns the CSV row and a one-line summary of the results.
+// If verbose, prints progress as each algorithm finishes.
+fn run_compare_core(
+    basename: &str,
+    algs: &[Algorithm],
+    timeout_secs: u64,
+    batch_size: Option<usize>,
+    verbose: bool,
+) -> (String, String) {
+    let example = Arc::new(Example::load_from(basename));
+    if verbose {
+        println!("===== {} =====", example.name());
+        println!("Example size: {}, timeout: {}s", example.len(), timeout_secs);
+        if let Some(batch_size) = batch_size {
+            println!("Batch size: {}", batch_size);
+        }
+    }
     let mut result =
         format!("{}, {}, {}", example.name(), example.len(), timeout_secs);
+    let mut summaries = Vec::new();
 
     let timeout = Duration::from_secs(timeout_secs);
     for &alg in algs {
-        let out = run_core(&example, alg, timeout, false);
+        let out = run_core(
+            &example,
+            alg,
+            timeout,
+            RunOptions { batch_size, ..Default::default() },
+        );
+        if verbose {
+            println!("{}: {}", alg, out.summary());
+        }
+        summaries.push(format!("{}: {}", alg, out.summary()));
         result += &format!(", {}", out.time_str());
-        if cfg!(debug_assertions) {
+        if has_space_column() {
             result += &format!(", {}", out.space_str());
         }
     }
@@ -215,15 +419,254 @@ pub fn run_compare(
     // println!("{}", result);
     // println!("{}", run_compare_csv_header(algs));
 
+    let summary = format!(
+        "{} (size {}) -- {}",
+        example.name(),
+        example.len(),
+        summaries.join("; ")
+    );
+    (result, summary)
+}
+
+/*
+    Benchmarking with repeated runs (see bench.rs)
+*/
+
+// Run each algorithm on the example warmup times, discarding the results,
+// then reps more times, and return statistics of the times for each
+// algorithm. If a run times out, the remaining runs of that algorithm are
+// skipped (and its statistics are None).
+pub fn run_bench(
+    basename: &str,
+    algs: &[Algorithm],
+    timeout_secs: u64,
+    warmup: usize,
+    reps: usize,
+) -> Vec<BenchSummary> {
+    assert!(reps > 0, "Number of repetitions must be positive");
+    let example = Arc::new(Example::load_from(basename));
+    println!("===== {} =====", example.name());
+    println!("Example size: {}, timeout: {}s", example.len(), timeout_secs);
+    println!("Warmup runs: {}, repetitions: {}", warmup, reps);
+    let timeout = Duration::from_secs(timeout_secs);
+    let mut result = Vec::new();
+    for &alg in algs {
+        let mut samples = Vec::with_capacity(reps);
+        for i in 0..(warmup + reps) {
+            let out = run_core(&example, alg, timeout, RunOptions::default());
+            if !out.is_timeout() && !out.is_correct() {
+                println!("Warning: {}: Wrong Output", alg);
+            }
+            match out.time_value() {
+                None => break,
+                Some(time) if i >= warmup => samples.push(time),
+                Some(_) => (),
+            }
+        }
+        let samples = (samples.len() == reps).then_some(samples.as_slice());
+        let summary = BenchSummary::new(example.name(), alg, warmup, samples);
+        println!("{}", summary);
+        result.push(summary);
+    }
+    result
+}
+
+/*
+    Latency of each kind of transaction (see latency.rs)
+*/
+
+// Run each algorithm on the example, timing each transaction, and return
+// the latency summaries for each algorithm and kind of transaction.
+// Algorithms that time out are skipped.
+pub fn run_latency(
+    basename: &str,
+    algs: &[Algorithm],
+    timeout_secs: u64,
+) -> Vec<LatencySummary> {
+    let example = Arc::new(Example::load_from(basename));
+    println!("===== {} =====", example.name());
+    println!("Example size: {}, timeout: {}s", example.len(), timeout_secs);
+    let timeout = Duration::from_secs(timeout_secs);
+    let mut result = Vec::new();
+    for &alg in algs {
+        let out = run_core(
+            &example,
+            alg,
+            timeout,
+            RunOptions { latency: true, ..Default::default() },
+        );
+        println!("{}: {}", alg, out.summary());
+        if let Some(latency) = out.get_latency() {
+            for summary in latency.summaries(example.name(), alg) {
+                println!("    {}", summary);
+                result.push(summary);
+            }
+        }
+    }
     result
 }
 
+/*
+    Performance comparison on many examples, optionally in parallel
+*/
+
+// Same as run_compare on each example, returning the CSV rows in the same
+// order as basenames.
+// With jobs > 1, examples are run on a pool of that many threads (all
+// algorithms for one example run on the same thread, one at a time), and
+// one line is printed per example as it finishes. Runs don't share any
+// data, but they do share the machine, so timings are noisier than with
+// jobs = 1 (serial mode), which should be used for timing-sensitive runs.
+pub fn run_compare_all(
+    basenames: &[String],
+    algs: &[Algorithm],
+    timeout_secs: u64,
+    batch_size: Option<usize>,
+    jobs: usize,
+) -> Vec<String> {
+    if jobs <= 1 {
+        return basenames
+            .iter()
+            .map(|basename| {
+                run_compare(basename, algs, timeout_secs, batch_size)
+            })
+            .collect();
+    }
+    let total = basenames.len();
+    let next = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+    let rows = Mutex::new(vec![None; total]);
+    thread::scope(|scope| {
+        for i in 0..jobs.min(total) {
+            thread::Builder::new()
+                .name(format!("worker {}", i))
+                // Same as the recommended ulimit -s (see README)
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, || loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= total {
+                        break;
+                    }
+                    let (row, summary) = run_compare_core(
+                        &basenames[i],
+                        algs,
+                        timeout_secs,
+                        batch_size,
+                        false,
+                    );
+                    rows.lock().unwrap()[i] = Some(row);
+                    let n = finished.fetch_add(1, Ordering::SeqCst) + 1;
+                    println!("[{}/{}] {}", n, total, summary);
+                })
+                .unwrap_or_else(|err| {
+                    panic!("Could not start worker thread: {}", err)
+                });
+        }
+    });
+    // A worker that panicked would have propagated out of the scope
+    rows.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+}
+
 /*
     Get all example basenames in a directory
 */
 
 pub fn example_basenames_in_dir(dir: &str) -> Vec<String> {
+    // An example may be stored in both formats; only include it once
+    let mut seen = HashSet::new();
     util::paths_in(dir)
-        .filter_map(|path| path.strip_suffix(EXAMPLE_IN_EXT).map(String::from))
+        .filter_map(|path| {
+            path.strip_suffix(EXAMPLE_IN_EXT)
+                .or_else(|| path.strip_suffix(EXAMPLE_IN_BIN_EXT))
+                .map(String::from)
+        })
+        .filter(|basename| seen.insert(basename.clone()))
         .collect()
 }
+
+/*
+    Select a subset of the examples (see run_all)
+*/
+
+// Whether dir is the same as or under parent
+fn is_in_dir(dir: &str, parent: &str) -> bool {
+    let parent = parent.trim_end_matches('/');
+    dir == parent
+        || dir.strip_prefix(parent).is_some_and(|rest| rest.starts_with('/'))
+}
+
+// The default filter selects all examples
+#[derive(Clone, Default)]
+pub struct ExampleFilter {
+    // Only directories that are the same as or under one of these (if any)
+    pub dirs: Vec<String>,
+    pub exclude_dirs: Vec<String>,
+    // Only examples whose basename matches one of these (if any)
+    pub patterns: Vec<Pattern>,
+    // Bounds on the number of transactions (checking these requires
+    // loading each example)
+    pub min_size: Option<usize>,
+    pub max_size: Option<usize>,
+    // Pick this many of the selected examples at random, using the seed
+    pub sample: Option<usize>,
+    pub seed: u64,
+}
+impl ExampleFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    fn selects_dir(&self, dir: &str) -> bool {
+        (self.dirs.is_empty() || self.dirs.iter().any(|d| is_in_dir(dir, d)))
+            && !self.exclude_dirs.iter().any(|d| is_in_dir(dir, d))
+    }
+    fn selects_example(&self, basename: &str) -> bool {
+        if !self.patterns.is_empty()
+            && !self.patterns.iter().any(|p| p.matches(basename))
+        {
+            return false;
+        }
+        if self.min_size.is_none() && self.max_size.is_none() {
+            return true;
+        }
+        let size = Example::load_from(basename).len();
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+    // The selected examples in each of the selected directories, in order.
+    // Panics if one of self.dirs doesn't match any of the directories.
+    pub fn select(&self, dirs: &[&str]) -> Vec<(String, Vec<String>)> {
+        for d in &self.dirs {
+            if !dirs.iter().any(|dir| is_in_dir(dir, d)) {
+                panic!("Not a known example directory: {}", d);
+            }
+        }
+        let mut selected: Vec<(String, Vec<String>)> = dirs
+            .iter()
+            .filter(|dir| self.selects_dir(dir))
+            .map(|&dir| {
+                let basenames = example_basenames_in_dir(dir)
+                    .into_iter()
+                    .filter(|basename| self.selects_example(basename))
+                    .collect();
+                (dir.to_string(), basenames)
+            })
+            .collect();
+        // Sampled from the sorted names, so that the sample only depends on
+        // the seed and the examples, not on the order of files on disk
+        let mut names: Vec<&String> =
+            selected.iter().flat_map(|(_, b)| b.iter()).collect();
+        if let Some(n) = self.sample.filter(|&n| n < names.len()) {
+            names.sort();
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            let keep: HashSet<String> =
+                rand::seq::index::sample(&mut rng, names.len(), n)
+                    .into_iter()
+                    .map(|i| names[i].clone())
+                    .collect();
+            for (_, basenames) in &mut selected {
+                basenames.retain(|basename| keep.contains(basename));
+            }
+        }
+        selected
+    }
+}
@@ -64,7 +64,7 @@ fn vert_id(v: IdType) -> NodeId {
 /*
     The publicly exposed data structure
 */
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct EulerForest {
     nodes: AvlForest,
 }
@@ -0,0 +1,112 @@
+/*
+    Status change events
+
+    A helper used by each algorithm to implement enable_events and
+    drain_events in the StateGraph interface.
+
+    The algorithm marks each vertex whose status it updates (in set_status).
+    Then drain() compares the current status of every marked vertex, and every
+    vertex merged with it, against the status that was last reported for it.
+    This way:
+    - changes that are undone before the next drain are not reported;
+    - vertices that changed status implicitly, by being merged into another
+      vertex (see graph.rs), are reported under their own name.
+
+    Does nothing (no overhead besides a branch) until enabled.
+*/
+
+use super::graph::DiGraph;
+use super::interface::{Status, StatusEvent, Vertex};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+#[derive(Clone, Debug)]
+pub struct StatusEvents<V> {
+    enabled: bool,
+    // Vertices updated since the last drain
+    dirty: Vec<V>,
+    // Set when all vertices may have changed (e.g. after pop)
+    all_dirty: bool,
+    // Last reported status of each vertex, if not Open
+    reported: HashMap<V, Status>,
+}
+impl<V> Default for StatusEvents<V> {
+    // Can't derive automatically because we don't want to assume V: Default
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dirty: Default::default(),
+            all_dirty: false,
+            reported: Default::default(),
+        }
+    }
+}
+impl<V: Vertex> StatusEvents<V> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn enable<T: Debug + PartialEq>(
+        &mut self,
+        graph: &DiGraph<V, T>,
+        status: impl Fn(&T) -> Status,
+    ) {
+        // The current statuses count as already reported
+        if !self.enabled {
+            self.enabled = true;
+            self.reported = graph
+                .iter_vertices_all()
+                .map(|v| (v, status(graph.get_label(v).unwrap())))
+                .filter(|&(_, st)| st != Status::Open)
+                .collect();
+        }
+    }
+    pub fn mark(&mut self, v: V) {
+        if self.enabled && !self.all_dirty {
+            self.dirty.push(v);
+        }
+    }
+    pub fn mark_all(&mut self) {
+        if self.enabled {
+            self.all_dirty = true;
+            self.dirty.clear();
+        }
+    }
+    pub fn drain<T: Debug + PartialEq>(
+        &mut self,
+        graph: &DiGraph<V, T>,
+        status: impl Fn(&T) -> Status,
+    ) -> Vec<StatusEvent<V>> {
+        let to_check: Vec<V> = if self.all_dirty {
+            self.all_dirty = false;
+            self.reported
+                .keys()
+                .copied()
+                .chain(graph.iter_vertices_all())
+                .collect()
+        } else {
+            self.dirty
+                .drain(..)
+                .filter(|&v| graph.is_seen(v))
+                .flat_map(|v| graph.iter_merged(v))
+                .collect()
+        };
+        let mut checked = HashSet::new();
+        let mut result = Vec::new();
+        for v in to_check {
+            if !checked.insert(v) {
+                continue;
+            }
+            let old = self.reported.get(&v).copied().unwrap_or_default();
+            let new = graph.get_label(v).map_or(Status::Open, &status);
+            if old != new {
+                result.push(StatusEvent { vertex: v, old, new });
+                if new == Status::Open {
+                    self.reported.remove(&v);
+                } else {
+                    self.reported.insert(v, new);
+                }
+            }
+        }
+        result
+    }
+}
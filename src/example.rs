@@ -3,18 +3,25 @@
     graph interface.
 
     Includes interfacing with file input/output and running an example using
-    a timeout.
+    a timeout. Inputs can be stored as JSON or, for large inputs, in the
+    compact binary format (see compact.rs), chosen by the file extension.
 
     Core types: ExampleInput, ExampleOutput, Example, and ExampleResult.
 */
 
-use super::constants::{EXAMPLE_EXPECT_EXT, EXAMPLE_IN_EXT};
+use super::compact;
+use super::constants::{
+    EXAMPLE_EXPECT_EXT, EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT,
+};
 use super::interface::{StateGraph, Status, Transaction};
+use super::latency::LatencyStats;
+use super::memory::PeakMemory;
 use super::util;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::path::PathBuf;
-use std::time::{Duration, SystemTime};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 /*
     ExampleInput
@@ -29,11 +36,27 @@ impl ExampleInput {
     pub fn push(&mut self, t: Transaction) {
         self.0.push(t);
     }
+    // Load or save using the format given by the file extension:
+    // compact binary for .bin, otherwise JSON
+    pub fn load_from_file(path: &Path) -> Self {
+        if is_compact_path(path) {
+            Self(compact::from_compact_file(path))
+        } else {
+            util::from_json_file(path)
+        }
+    }
+    pub fn save_to_file(&self, path: &Path) {
+        if is_compact_path(path) {
+            compact::to_compact_file(path, &self.0);
+        } else {
+            util::to_json_file(path, self);
+        }
+    }
     pub fn get_states(&self) -> HashSet<usize> {
         let mut result = HashSet::new();
         for &t in &self.0 {
             match t {
-                Transaction::Add(v1, v2) => {
+                Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
                     result.insert(v1);
                     result.insert(v2);
                 }
@@ -88,70 +111,144 @@ pub struct DebugStats {
     correct: bool,
     time: usize,
     space: usize,
+    latency: Option<LatencyStats>,
 }
 pub struct ReleaseStats {
     output: ExampleOutput,
     correct: bool,
     time: Duration,
+    // Peak bytes allocated, if tracked (see memory.rs)
+    space: Option<usize>,
+    // Time of each transaction, if tracked (see latency.rs)
+    latency: Option<LatencyStats>,
 }
 pub enum ExampleResult {
-    Timeout,
+    // Number of transactions that had completed when the timeout expired
+    Timeout(usize),
     Debug(DebugStats),
     Release(ReleaseStats),
 }
 impl ExampleResult {
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
     pub fn is_correct(&self) -> bool {
         match self {
-            Self::Timeout => false,
+            Self::Timeout(_) => false,
             Self::Debug(res) => res.correct,
             Self::Release(res) => res.correct,
         }
     }
     pub fn summary(&self) -> String {
-        if let Self::Timeout = self {
-            "Timeout".to_string()
+        if let &Self::Timeout(completed) = self {
+            format!("Timeout (after {} transactions)", completed)
         } else if !self.is_correct() {
             "Wrong Output".to_string()
         } else if let Self::Debug(res) = self {
             format!("time {}, space {}", res.time, res.space)
         } else if let Self::Release(res) = self {
-            format!("time {}ms", res.time.as_millis())
+            match res.space {
+                Some(space) => format!(
+                    "time {}ms, space {} bytes",
+                    res.time.as_millis(),
+                    space
+                ),
+                None => format!("time {}ms", res.time.as_millis()),
+            }
         } else {
             unreachable!()
         }
     }
+    // As in the summary, a wrong output is reported instead of the time
     pub fn time_str(&self) -> String {
         match self {
-            Self::Timeout => "Timeout".to_string(),
+            Self::Timeout(_) => "Timeout".to_string(),
+            _ if !self.is_correct() => "Wrong Output".to_string(),
             Self::Debug(res) => format!("{}", res.time),
             Self::Release(res) => format!("{}", res.time.as_millis()),
         }
     }
+    // Time in the same units as time_str (in release mode, milliseconds,
+    // but with fractions), or None on a timeout
+    pub fn time_value(&self) -> Option<f64> {
+        match self {
+            Self::Timeout(_) => None,
+            Self::Debug(res) => Some(res.time as f64),
+            Self::Release(res) => Some(res.time.as_secs_f64() * 1000.0),
+        }
+    }
     pub fn space_str(&self) -> String {
         match self {
-            Self::Timeout => "Timeout".to_string(),
+            Self::Timeout(_) => "Timeout".to_string(),
             Self::Debug(res) => format!("{}", res.space),
-            Self::Release(_) => "Unknown (not tracked)".to_string(),
+            Self::Release(res) => match res.space {
+                Some(space) => format!("{}", space),
+                None => "Unknown (not tracked)".to_string(),
+            },
         }
     }
     pub fn get_output(&self) -> Option<&ExampleOutput> {
         match self {
-            Self::Timeout => None,
+            Self::Timeout(_) => None,
             Self::Debug(res) => Some(&res.output),
             Self::Release(res) => Some(&res.output),
         }
     }
+    pub fn get_latency(&self) -> Option<&LatencyStats> {
+        match self {
+            Self::Timeout(_) => None,
+            Self::Debug(res) => res.latency.as_ref(),
+            Self::Release(res) => res.latency.as_ref(),
+        }
+    }
     pub fn output_str(&self) -> String {
         match self {
-            Self::Timeout => "Timeout".to_string(),
+            Self::Timeout(_) => "Timeout".to_string(),
             Self::Debug(res) => format!("{:?}", res.output),
             Self::Release(res) => format!("{:?}", res.output),
         }
     }
 }
 
+/*
+    Progress of a run, shared with another thread (see run_with_progress)
+*/
+
+#[derive(Debug, Default)]
+pub struct Progress {
+    completed: AtomicUsize,
+    cancelled: AtomicBool,
+}
+impl Progress {
+    pub fn completed(&self) -> usize {
+        self.completed.load(Ordering::Relaxed)
+    }
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+fn is_compact_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "bin")
+}
 fn infile_path(basename: &str) -> PathBuf {
-    PathBuf::from(format!("{}{}", basename, EXAMPLE_IN_EXT))
+    // If the example has been converted (see convert_input.rs), use the
+    // newer file, so that a stale copy never shadows an edited one; on a
+    // tie, prefer the compact format
+    let json_path = PathBuf::from(format!("{}{}", basename, EXAMPLE_IN_EXT));
+    let bin_path = PathBuf::from(format!("{}{}", basename, EXAMPLE_IN_BIN_EXT));
+    let use_bin = util::file_exists(&bin_path)
+        && (!util::file_exists(&json_path)
+            || util::modified_time(&bin_path)
+                >= util::modified_time(&json_path));
+    if use_bin {
+        bin_path
+    } else {
+        json_path
+    }
 }
 fn expectfile_path(basename: &str) -> PathBuf {
     PathBuf::from(format!("{}{}", basename, EXAMPLE_EXPECT_EXT))
@@ -183,7 +280,7 @@ impl Example {
         // Panics if infile (self.infile_path()) does not exist
         let infile = infile_path(basename);
         let expectfile = expectfile_path(basename);
-        let input = util::from_json_file(&infile);
+        let input = ExampleInput::load_from_file(&infile);
         let expected = if util::file_exists(&expectfile) {
             util::from_json_file(&expectfile)
         } else {
@@ -192,9 +289,9 @@ impl Example {
         Self::new(basename, input, expected)
     }
     pub fn save(&self) {
-        util::to_json_file(infile_path(&self.basename), &self.input);
+        self.input.save_to_file(&infile_path(&self.basename));
         if let Some(expect) = &self.expected {
-            util::to_json_file(expectfile_path(&self.basename), &expect);
+            util::to_json_file(expectfile_path(&self.basename), expect);
         }
     }
 
@@ -202,30 +299,88 @@ impl Example {
     // it matches the expected output.
     // Additionally enforces a timeout (Duration), although
     // only at the granularity of transactions.
+    // (For a timeout that doesn't wait for the current transaction, see
+    // driver::run_preemptive.)
     pub fn run_with_timeout<G: StateGraph + ?Sized>(
         &self,
         graph: &mut G,
         timeout: Duration,
     ) -> ExampleResult {
+        self.run_batched_with_timeout(graph, timeout, None)
+    }
+    // Same, but if a batch size is given, process the transactions in
+    // batches of that size using process_batch (and only check the timeout
+    // between batches).
+    pub fn run_batched_with_timeout<G: StateGraph + ?Sized>(
+        &self,
+        graph: &mut G,
+        timeout: Duration,
+        batch_size: Option<usize>,
+    ) -> ExampleResult {
+        let progress = Progress::default();
+        self.run_with_progress(graph, timeout, batch_size, false, &progress)
+    }
+    // Same, but also report the number of completed transactions in
+    // progress as they complete, and stop (with a timeout) if progress is
+    // cancelled from another thread.
+    // If track_latency, also time each transaction (see latency.rs); this
+    // adds some overhead to the total time, and is skipped if transactions
+    // are processed in batches.
+    pub fn run_with_progress<G: StateGraph + ?Sized>(
+        &self,
+        graph: &mut G,
+        timeout: Duration,
+        batch_size: Option<usize>,
+        track_latency: bool,
+        progress: &Progress,
+    ) -> ExampleResult {
+        // Allocated first so that it doesn't count towards the space
+        let mut latency = if track_latency && batch_size.is_none() {
+            Some(LatencyStats::new())
+        } else {
+            None
+        };
+        let memory = PeakMemory::start();
         let start = SystemTime::now();
-        for &t in &self.input.0 {
+        let mut completed = 0;
+        for batch in self.input.0.chunks(batch_size.unwrap_or(1)) {
             let time_elapsed = util::time_since(&start);
-            if time_elapsed > timeout {
-                return ExampleResult::Timeout;
+            if time_elapsed > timeout || progress.is_cancelled() {
+                return ExampleResult::Timeout(completed);
+            }
+            if batch_size.is_some() {
+                graph.process_batch(batch);
+            } else if let Some(latency) = &mut latency {
+                let t_start = Instant::now();
+                graph.process(batch[0]);
+                latency.record(batch[0], t_start.elapsed());
+            } else {
+                graph.process(batch[0]);
             }
-            graph.process(t);
+            completed += batch.len();
+            progress.completed.store(completed, Ordering::Relaxed);
         }
         let total_elapsed = util::time_since(&start);
+        // Measured before collecting the output, which also allocates
+        let space = memory.bytes();
         let (output, correct) = self.collect_output(graph);
         if cfg!(debug_assertions) {
             let time = graph.get_time();
             let space = graph.get_space();
-            ExampleResult::Debug(DebugStats { output, correct, time, space })
+            ExampleResult::Debug(DebugStats {
+                output,
+                correct,
+                time,
+                space,
+                latency,
+            })
         } else {
             ExampleResult::Release(ReleaseStats {
                 output,
                 correct,
                 time: total_elapsed,
+                space,
+                latency,
             })
         }
     }
@@ -0,0 +1,161 @@
+/*
+    C API, for using the algorithms from C or C++ (e.g. from a solver like
+    z3; compare algorithm/simple.rs with z3/src/util/state_graph.h).
+
+    The crate is also built as a static and dynamic library, and the
+    header include/guided_digraph.h is generated from this file by cbindgen
+    (the test in tests/test_ffi.rs checks that it is up to date, and builds
+    and runs the C test program in tests/ffi/).
+
+    A graph is an opaque handle, created with gid_new and freed with
+    gid_free. States are numbers (size_t). Updates return GID_OK, or
+    GID_ERROR if the update is not allowed (e.g. adding a transition from a
+    closed state), in which case the graph is unchanged. None of the
+    functions panic on invalid input. A graph must not be used from two
+    threads at once.
+*/
+
+use super::driver::Algorithm;
+use super::interface::{StateGraph, Status, Transaction};
+use std::ptr;
+
+pub const GID_ALG_NAIVE: u32 = 0;
+pub const GID_ALG_SIMPLE: u32 = 1;
+pub const GID_ALG_BFGT: u32 = 2;
+pub const GID_ALG_LOG: u32 = 3;
+pub const GID_ALG_JUMP: u32 = 4;
+
+pub const GID_OK: i32 = 0;
+pub const GID_ERROR: i32 = -1;
+
+/// Status of a state; states that have not been seen are GID_OPEN.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GidStatus {
+    GidLive,
+    GidDead,
+    GidUnknown,
+    GidOpen,
+}
+impl From<Status> for GidStatus {
+    fn from(st: Status) -> Self {
+        match st {
+            Status::Live => GidStatus::GidLive,
+            Status::Dead => GidStatus::GidDead,
+            Status::Unknown => GidStatus::GidUnknown,
+            Status::Open => GidStatus::GidOpen,
+        }
+    }
+}
+
+/// Opaque handle to a state graph
+pub struct GidGraph {
+    graph: Box<dyn StateGraph>,
+}
+
+fn algorithm(alg: u32) -> Option<Algorithm> {
+    match alg {
+        GID_ALG_NAIVE => Some(Algorithm::Naive),
+        GID_ALG_SIMPLE => Some(Algorithm::Simple),
+        GID_ALG_BFGT => Some(Algorithm::BFGT),
+        GID_ALG_LOG => Some(Algorithm::Log),
+        GID_ALG_JUMP => Some(Algorithm::Jump),
+        _ => None,
+    }
+}
+
+// Process t if it is valid (see can_process), as a return code
+unsafe fn update(g: *mut GidGraph, t: Transaction) -> i32 {
+    match g.as_mut() {
+        Some(g) if g.graph.can_process(t) => {
+            g.graph.process(t);
+            GID_OK
+        }
+        _ => GID_ERROR,
+    }
+}
+
+/*
+    Exposed API
+
+    The functions taking a graph are unsafe since g must be NULL or a
+    graph returned by gid_new that was not yet freed.
+*/
+
+/// Create a new, empty graph using one of the GID_ALG_* algorithms.
+/// Returns NULL if the algorithm is not valid.
+#[no_mangle]
+pub extern "C" fn gid_new(alg: u32) -> *mut GidGraph {
+    match algorithm(alg) {
+        Some(alg) => {
+            Box::into_raw(Box::new(GidGraph { graph: alg.new_graph() }))
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free a graph created by gid_new. Does nothing if g is NULL.
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_free(g: *mut GidGraph) {
+    if !g.is_null() {
+        drop(Box::from_raw(g));
+    }
+}
+
+/// Add a transition from v1 to v2, where v1 is Open or Live.
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_add_transition(
+    g: *mut GidGraph,
+    v1: usize,
+    v2: usize,
+) -> i32 {
+    update(g, Transaction::Add(v1, v2))
+}
+
+/// Mark v closed, where v is Open or Live.
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_mark_closed(g: *mut GidGraph, v: usize) -> i32 {
+    update(g, Transaction::Close(v))
+}
+
+/// Mark v live, where v is Open or Live.
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_mark_live(g: *mut GidGraph, v: usize) -> i32 {
+    update(g, Transaction::Live(v))
+}
+
+/// Status of v (GID_OPEN if v has not been seen, or if g is NULL).
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_get_status(
+    g: *const GidGraph,
+    v: usize,
+) -> GidStatus {
+    match g.as_ref() {
+        Some(g) => g.graph.get_status(v).unwrap_or(Status::Open).into(),
+        None => GidStatus::GidOpen,
+    }
+}
+
+/// Whether v is Dead (same as gid_get_status(g, v) == GID_DEAD).
+///
+/// # Safety
+/// g must be NULL or a graph returned by gid_new that was not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn gid_is_dead(g: *const GidGraph, v: usize) -> bool {
+    gid_get_status(g, v) == GidStatus::GidDead
+}
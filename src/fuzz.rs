@@ -0,0 +1,584 @@
+/*
+    Differential fuzzing of the algorithms
+
+    The examples in examples/ only compare the algorithms on fixed inputs,
+    and only on the final output. Here we generate random transaction
+    sequences instead, run several algorithms on each one side by side, and
+    check that they agree on the status of every state after every
+    transaction (as in example.rs, unseen states count as Open), and that
+    their invariants hold (see invariants.rs; these are slower to check, so
+    only every few transactions).
+
+    Generated sequences are valid: Add, Close, and Live are only applied to
+    states that are still Open, i.e. that were not already closed or marked
+    live. Removals are optional, since only some algorithms support them
+    (see Algorithm::supports_remove).
+
+    When a sequence fails (the algorithms disagree, or one of them panics or
+    violates an invariant), it is shrunk by deleting transactions for as
+    long as it still fails, and the states are renumbered from 0. Deleting
+    transactions keeps a generated sequence valid, since it only leaves more
+    states Open.
+    See the fuzz binary, which saves the shrunk inputs as examples.
+
+    The same shrinking also minimizes existing examples on which two
+    algorithms give different outputs (see the minimize binary). Examples
+    may also apply Add, Close, and Live to states that are already Live,
+    which does nothing, but could become invalid once other transactions
+    are deleted; so these are dropped first (see drop_redundant).
+*/
+
+use super::algorithm::LogStateGraph;
+use super::driver::{self, Algorithm};
+use super::example::{Example, ExampleInput};
+use super::interface::{StateGraph, Status, Transaction};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+/*
+    Generating random transaction sequences
+*/
+
+#[derive(Clone, Copy, Debug)]
+pub struct FuzzConfig {
+    // States are chosen from 0..states
+    pub states: usize,
+    // Maximum number of transactions (fewer if all states get closed)
+    pub len: usize,
+    // Whether to generate Remove transactions
+    pub remove: bool,
+}
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self { states: 10, len: 50, remove: false }
+    }
+}
+impl FuzzConfig {
+    // The algorithms that can run the generated sequences
+    pub fn algs(&self) -> Vec<Algorithm> {
+        let all = driver::ALL_ALGS.iter().copied();
+        all.filter(|alg| !self.remove || alg.supports_remove()).collect()
+    }
+}
+
+pub fn random_transactions(seed: u64, config: &FuzzConfig) -> Vec<Transaction> {
+    random_transactions_with(seed, config, |_| true)
+}
+
+// As random_transactions, but skip each generated transaction for which
+// allow returns false (allow is called on the others in order, so it can
+// track the sequence so far)
+fn random_transactions_with<F>(
+    seed: u64,
+    config: &FuzzConfig,
+    mut allow: F,
+) -> Vec<Transaction>
+where
+    F: FnMut(Transaction) -> bool,
+{
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut result = Vec::new();
+    // States that are no longer Open, and transitions that can be removed
+    let mut closed = HashSet::new();
+    let mut edges = Vec::new();
+    // Give up if allow rejects too much (e.g. all remaining removals)
+    let mut rejected = 0;
+    while result.len() < config.len && rejected <= 10 * config.len {
+        let can_remove = config.remove && !edges.is_empty();
+        if closed.len() == config.states && !can_remove {
+            break;
+        }
+        let v1 = rng.gen_range(0..config.states);
+        let v2 = rng.gen_range(0..config.states);
+        let open = !closed.contains(&v1);
+        let t = match rng.gen_range(0..10) {
+            0..=4 if open => Transaction::Add(v1, v2),
+            5..=7 if open => Transaction::Close(v1),
+            8 if open => Transaction::Live(v1),
+            9 if can_remove => {
+                let (v1, v2) = edges[rng.gen_range(0..edges.len())];
+                Transaction::Remove(v1, v2)
+            }
+            _ => continue,
+        };
+        if !allow(t) {
+            rejected += 1;
+            continue;
+        }
+        match t {
+            Transaction::Add(v1, v2) => edges.push((v1, v2)),
+            Transaction::Close(v1) | Transaction::Live(v1) => {
+                closed.insert(v1);
+            }
+            Transaction::Remove(v1, v2) => edges.retain(|&e| e != (v1, v2)),
+        }
+        result.push(t);
+    }
+    result
+}
+
+/*
+    Running the algorithms side by side
+*/
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    // The algorithms disagree on a state after transaction number step
+    Mismatch { step: usize, vertex: usize, statuses: Vec<(Algorithm, Status)> },
+    // An algorithm panicked during transaction number step
+    Panic { step: usize, alg: Algorithm, message: String },
+    // An algorithm's invariants (see invariants.rs) are violated after
+    // transaction number step
+    Invariant { step: usize, alg: Algorithm, message: String },
+}
+impl Failure {
+    pub fn step(&self) -> usize {
+        match self {
+            Self::Mismatch { step, .. }
+            | Self::Panic { step, .. }
+            | Self::Invariant { step, .. } => *step,
+        }
+    }
+}
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Mismatch { step, vertex, statuses } => {
+                write!(f, "After step {}, status of {}:", step, vertex)?;
+                for (alg, status) in statuses {
+                    write!(f, " {:?} ({})", status, alg)?;
+                }
+                Ok(())
+            }
+            Self::Panic { step, alg, message } => {
+                write!(f, "At step {}, {} panicked: {}", step, alg, message)
+            }
+            Self::Invariant { step, alg, message } => write!(
+                f,
+                "After step {}, invariant of {} violated: {}",
+                step, alg, message
+            ),
+        }
+    }
+}
+
+fn states_of(transactions: &[Transaction]) -> Vec<usize> {
+    let mut states: Vec<usize> = transactions
+        .iter()
+        .flat_map(|&t| match t {
+            Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
+                vec![v1, v2]
+            }
+            Transaction::Close(v) | Transaction::Live(v) => vec![v],
+        })
+        .collect();
+    states.sort_unstable();
+    states.dedup();
+    states
+}
+
+fn panic_message(err: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = err.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = err.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "(no message)".to_string()
+    }
+}
+
+// Checking invariants takes linear time or worse, so check them only after
+// every INVARIANT_INTERVAL transactions, and after the last one
+const INVARIANT_INTERVAL: usize = 10;
+
+// Run the algorithms on the transactions, and return the first step after
+// which they disagree or one of their invariants is found to be violated,
+// if any.
+// A panic in an algorithm is caught and reported (the default panic hook
+// still prints it, see the fuzz binary).
+pub fn check(
+    transactions: &[Transaction],
+    algs: &[Algorithm],
+) -> Result<(), Failure> {
+    let states = states_of(transactions);
+    let mut graphs: Vec<Box<dyn StateGraph>> =
+        algs.iter().map(|alg| alg.new_graph()).collect();
+    for (step, &t) in transactions.iter().enumerate() {
+        for (&alg, graph) in algs.iter().zip(graphs.iter_mut()) {
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| graph.process(t)));
+            if let Err(err) = result {
+                let message = panic_message(err.as_ref());
+                return Err(Failure::Panic { step, alg, message });
+            }
+            let due = (step + 1) % INVARIANT_INTERVAL == 0
+                || step + 1 == transactions.len();
+            if !due {
+                continue;
+            }
+            if let Err(message) = graph.check_invariants() {
+                return Err(Failure::Invariant { step, alg, message });
+            }
+        }
+        for &vertex in &states {
+            let statuses: Vec<(Algorithm, Status)> = algs
+                .iter()
+                .zip(graphs.iter())
+                .map(|(&alg, g)| {
+                    (alg, g.get_status(vertex).unwrap_or_default())
+                })
+                .collect();
+            if statuses.iter().any(|&(_, st)| st != statuses[0].1) {
+                return Err(Failure::Mismatch { step, vertex, statuses });
+            }
+        }
+    }
+    Ok(())
+}
+
+/*
+    Shrinking a failing sequence
+*/
+
+// Delete chunks of the given size wherever the result still fails, and
+// return whether anything was deleted
+fn delete_chunks<F>(
+    current: &mut Vec<Transaction>,
+    chunk: usize,
+    fails: &mut F,
+) -> bool
+where
+    F: FnMut(&[Transaction]) -> bool,
+{
+    let mut deleted = false;
+    let mut i = 0;
+    while i < current.len() {
+        let end = (i + chunk).min(current.len());
+        let candidate: Vec<Transaction> =
+            current[..i].iter().chain(&current[end..]).copied().collect();
+        if fails(&candidate) {
+            *current = candidate;
+            deleted = true;
+        } else {
+            i = end;
+        }
+    }
+    deleted
+}
+
+// Rename the states to 0, 1, 2, ... in order of first appearance
+pub fn renumber(transactions: &[Transaction]) -> Vec<Transaction> {
+    let mut names: HashMap<usize, usize> = HashMap::new();
+    let mut name = |v: usize| {
+        let next = names.len();
+        *names.entry(v).or_insert(next)
+    };
+    transactions
+        .iter()
+        .map(|&t| match t {
+            Transaction::Add(v1, v2) => {
+                let v1 = name(v1);
+                Transaction::Add(v1, name(v2))
+            }
+            Transaction::Remove(v1, v2) => {
+                let v1 = name(v1);
+                Transaction::Remove(v1, name(v2))
+            }
+            Transaction::Close(v) => Transaction::Close(name(v)),
+            Transaction::Live(v) => Transaction::Live(name(v)),
+        })
+        .collect()
+}
+
+// Shrink a sequence for which fails returns true to one where deleting
+// any single transaction makes it pass (delta debugging: first delete
+// large chunks, then smaller ones), then renumber the states if it still
+// fails after that.
+pub fn shrink<F>(transactions: &[Transaction], mut fails: F) -> Vec<Transaction>
+where
+    F: FnMut(&[Transaction]) -> bool,
+{
+    let mut current = transactions.to_vec();
+    let mut chunk = current.len().div_ceil(2).max(1);
+    loop {
+        let deleted = delete_chunks(&mut current, chunk, &mut fails);
+        if chunk > 1 {
+            chunk /= 2;
+        } else if !deleted {
+            break;
+        }
+    }
+    let renumbered = renumber(&current);
+    if fails(&renumbered) {
+        renumbered
+    } else {
+        current
+    }
+}
+
+/*
+    Minimizing an example on which two algorithms disagree
+*/
+
+// Drop each Add, Close, or Live from a state that was already closed or
+// marked live (so that the result stays valid after deleting any of its
+// transactions), and each Add of a self-loop.
+// Without Remove transactions, all of these do nothing: a valid example
+// only has them for states that are Live.
+pub fn drop_redundant(transactions: &[Transaction]) -> Vec<Transaction> {
+    let mut closed = HashSet::new();
+    transactions
+        .iter()
+        .copied()
+        .filter(|&t| match t {
+            Transaction::Add(v1, v2) => v1 != v2 && !closed.contains(&v1),
+            Transaction::Close(v) | Transaction::Live(v) => closed.insert(v),
+            Transaction::Remove(_, _) => true,
+        })
+        .collect()
+}
+
+// Whether the two algorithms both finish within the timeout and give
+// different outputs. A panic counts as not finishing (the default panic
+// hook still prints it).
+// Shrinking calls this many times, so each algorithm runs on the current
+// thread, and the timeout is only checked between transactions (see
+// Example::run_with_timeout): with driver::run_preemptive, each run that
+// timed out would leave a worker thread running.
+pub fn outputs_differ(
+    transactions: &[Transaction],
+    alg1: Algorithm,
+    alg2: Algorithm,
+    timeout: Duration,
+) -> bool {
+    let input = ExampleInput(transactions.to_vec());
+    let example = Example::new("minimize", input, None);
+    let run = |alg: Algorithm| {
+        panic::catch_unwind(|| {
+            let mut graph = alg.new_graph();
+            example.run_with_timeout(graph.as_mut(), timeout)
+        })
+        .ok()
+    };
+    let (result1, result2) = match (run(alg1), run(alg2)) {
+        (Some(result1), Some(result2)) => (result1, result2),
+        _ => return false,
+    };
+    match (result1.get_output(), result2.get_output()) {
+        (Some(out1), Some(out2)) => out1 != out2,
+        _ => false,
+    }
+}
+
+/*
+    Putting it together
+*/
+
+// A failing sequence found by fuzz, before and after shrinking
+#[derive(Clone, Debug)]
+pub struct FuzzFailure {
+    pub seed: u64,
+    pub original: Vec<Transaction>,
+    pub shrunk: Vec<Transaction>,
+    // The failure on the shrunk sequence
+    pub failure: Failure,
+}
+
+// Generate and check one sequence for each seed in seeds, stopping at the
+// first that fails
+pub fn fuzz<I>(seeds: I, config: &FuzzConfig) -> Option<FuzzFailure>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let algs = config.algs();
+    for seed in seeds {
+        let original = random_transactions(seed, config);
+        if check(&original, &algs).is_ok() {
+            continue;
+        }
+        let shrunk = shrink(&original, |ts| check(ts, &algs).is_err());
+        let failure = check(&shrunk, &algs).unwrap_err();
+        return Some(FuzzFailure { seed, original, shrunk, failure });
+    }
+    None
+}
+
+/*
+    Removals in LogStateGraph
+
+    Log only supports removing transitions from states that are not Live
+    and were not merged into a cycle (see can_remove_from in log.rs), so it
+    is left out of FuzzConfig::algs when generating removals. Instead, here
+    the removals are generated by running Log alongside, so that they all
+    satisfy this, and Log is compared against the algorithms that support
+    all removals.
+*/
+
+pub const LOG_REMOVE_ALGS: &[Algorithm] =
+    &[Algorithm::Naive, Algorithm::Simple, Algorithm::Log];
+
+// Whether Log supports each removal in the sequence. Shrinking needs this:
+// deleting transactions can change which states are merged.
+// If Log panics, the rest of the sequence counts as supported, so that
+// check reports the panic.
+pub fn log_supports(transactions: &[Transaction]) -> bool {
+    let mut log = LogStateGraph::new();
+    for &t in transactions {
+        if let Transaction::Remove(v1, _) = t {
+            if !log.can_remove_from(v1) {
+                return false;
+            }
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| log.process(t)));
+        if result.is_err() {
+            return true;
+        }
+    }
+    true
+}
+
+// As random_transactions with removals (whatever config.remove is), but
+// only those that Log supports
+pub fn random_log_transactions(
+    seed: u64,
+    config: &FuzzConfig,
+) -> Vec<Transaction> {
+    let config = FuzzConfig { remove: true, ..*config };
+    let mut log = Some(LogStateGraph::new());
+    random_transactions_with(seed, &config, |t| {
+        let graph = match &mut log {
+            Some(graph) => graph,
+            None => return true,
+        };
+        if let Transaction::Remove(v1, _) = t {
+            if !graph.can_remove_from(v1) {
+                return false;
+            }
+        }
+        // As in log_supports, stop checking after a panic
+        if panic::catch_unwind(AssertUnwindSafe(|| graph.process(t))).is_err() {
+            log = None;
+        }
+        true
+    })
+}
+
+// As fuzz, on sequences from random_log_transactions
+pub fn fuzz_log_remove<I>(seeds: I, config: &FuzzConfig) -> Option<FuzzFailure>
+where
+    I: IntoIterator<Item = u64>,
+{
+    let algs = LOG_REMOVE_ALGS;
+    for seed in seeds {
+        let original = random_log_transactions(seed, config);
+        if check(&original, algs).is_ok() {
+            continue;
+        }
+        let shrunk = shrink(&original, |ts| {
+            log_supports(ts) && check(ts, algs).is_err()
+        });
+        let failure = check(&shrunk, algs).unwrap_err();
+        return Some(FuzzFailure { seed, original, shrunk, failure });
+    }
+    None
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Transaction::*;
+
+    #[test]
+    fn test_random_valid() {
+        let config = FuzzConfig { states: 5, len: 100, remove: true };
+        for seed in 0..20 {
+            let ts = random_transactions(seed, &config);
+            assert_eq!(ts, random_transactions(seed, &config));
+            let mut closed = HashSet::new();
+            for t in ts {
+                match t {
+                    Add(v, _) => assert!(!closed.contains(&v)),
+                    Close(v) | Live(v) => assert!(closed.insert(v)),
+                    Remove(_, _) => (),
+                }
+            }
+        }
+        assert!(!config.algs().contains(&Algorithm::Jump));
+    }
+
+    #[test]
+    fn test_random_log_supported() {
+        let config = FuzzConfig { states: 8, len: 100, remove: false };
+        let mut removals = 0;
+        for seed in 0..20 {
+            let ts = random_log_transactions(seed, &config);
+            assert!(log_supports(&ts));
+            removals += ts.iter().filter(|t| matches!(t, Remove(..))).count();
+        }
+        assert!(removals > 0);
+        // Not supported: removal from a state merged into a cycle
+        let ts = [Add(0, 1), Add(1, 0), Add(2, 1), Close(0), Close(1)];
+        assert!(log_supports(&ts));
+        assert!(log_supports(&[&ts[..], &[Remove(2, 1)]].concat()));
+        assert!(!log_supports(&[&ts[..], &[Remove(1, 0)]].concat()));
+    }
+
+    #[test]
+    fn test_shrink() {
+        // Fails if 7 is closed after an edge into it
+        let fails = |ts: &[Transaction]| {
+            let add = ts.iter().position(|&t| t == Add(3, 7));
+            let close = ts.iter().position(|&t| t == Close(7));
+            matches!((add, close), (Some(i), Some(j)) if i < j)
+        };
+        let ts: Vec<Transaction> = (0..5)
+            .flat_map(|i| [Add(i, i + 1), Close(i)])
+            .chain([Add(3, 7), Live(8), Close(7), Close(5)])
+            .collect();
+        assert!(fails(&ts));
+        assert_eq!(shrink(&ts, fails), vec![Add(3, 7), Close(7)]);
+        // Renumbered when possible
+        let fails = |ts: &[Transaction]| ts.len() >= 2;
+        assert_eq!(shrink(&ts, fails), vec![Live(0), Close(1)]);
+    }
+
+    #[test]
+    fn test_drop_redundant() {
+        let ts = vec![
+            Add(0, 1),
+            Live(1),
+            Add(0, 0),
+            Add(0, 2),
+            Close(0),
+            Close(1),
+            Add(1, 3),
+            Remove(0, 1),
+            Close(0),
+        ];
+        let expected =
+            vec![Add(0, 1), Live(1), Add(0, 2), Close(0), Remove(0, 1)];
+        assert_eq!(drop_redundant(&ts), expected);
+    }
+
+    #[test]
+    fn test_check() {
+        let algs = FuzzConfig::default().algs();
+        let ts = vec![Add(0, 1), Add(1, 0), Close(0), Close(1)];
+        assert_eq!(check(&ts, &algs), Ok(()));
+        // BFGT doesn't support removal, so it panics
+        let ts = vec![Add(0, 1), Remove(0, 1)];
+        match check(&ts, &algs) {
+            Err(Failure::Panic { step, alg, .. }) => {
+                assert_eq!((step, alg), (1, Algorithm::BFGT))
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -11,10 +11,15 @@
     - Merging vertices in O(1) time (the two vertex names are now aliases)
       (requires a merge function T x T -> T)
       Note: this is a simple graph. self-loops are ignored after a merge.
+    - Iterating through all vertices merged with a vertex (O(1) per vertex)
+    - Removing edges (O(degree))
     - Iterating through the edges at a vertex (O(1) per edge)
       Note: this iterates over original edges; currently doesn't
       support "cleaning" edges by removing duplicates and self-loops
       in case of merged vertices.
+    - Iterating through all edges between the original vertices, even after
+      they are merged; and, if enabled, those from one original vertex
+      (used to extract certificates, see certificate.rs)
     - Generic search functions: DFS forward and backward, or topological
       search backward. For more documentation on these, see search.rs.
 
@@ -29,6 +34,7 @@ use std::collections::{HashMap, LinkedList};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::iter;
+use std::mem;
 
 // Newtypes to keep different types of ID straight
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -36,14 +42,32 @@ struct UniqueID(usize);
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 struct CanonicalID(usize);
 
-#[derive(Debug)]
+// Edges remember the original (unmerged) vertices at both ends: 'near' is
+// the vertex whose edge list the edge is in, 'far' is the other end
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Edge {
+    near: UniqueID,
+    far: UniqueID,
+}
+
+#[derive(Clone, Debug)]
 pub struct DiGraph<V, T> {
     vertex_ids: HashMap<V, UniqueID>,
     id_vertices: HashMap<UniqueID, V>,
     id_find: UnionFind<usize>,
+    // Vertices merged together form a circular list, so that the merged
+    // vertices can be enumerated (see iter_merged)
+    id_next: HashMap<UniqueID, UniqueID>,
     labels: HashMap<CanonicalID, T>,
-    fwd_edges: HashMap<CanonicalID, LinkedList<UniqueID>>,
-    bck_edges: HashMap<CanonicalID, LinkedList<UniqueID>>,
+    fwd_edges: HashMap<CanonicalID, LinkedList<Edge>>,
+    bck_edges: HashMap<CanonicalID, LinkedList<Edge>>,
+    // For each original vertex (indexed by UniqueID), the near ends of the
+    // bck-edges whose far end it is, i.e. the original targets of its
+    // transitions (see iter_bck_edges_orig_from). This costs time and space
+    // for every edge, so it is only kept once enabled.
+    bck_edges_orig_from: Option<Vec<Vec<UniqueID>>>,
+    // Merges since the last drain_merges, if enabled (for tracing)
+    merge_log: Option<Vec<(V, V)>>,
     // Debug mode statistics
     space: DebugCounter,
     time: DebugCounter,
@@ -56,9 +80,12 @@ impl<V, T> Default for DiGraph<V, T> {
             vertex_ids: Default::default(),
             id_vertices: Default::default(),
             id_find: Default::default(),
+            id_next: Default::default(),
             labels: Default::default(),
             fwd_edges: Default::default(),
             bck_edges: Default::default(),
+            bck_edges_orig_from: None,
+            merge_log: None,
             space: Default::default(),
             time: Default::default(),
         }
@@ -103,6 +130,12 @@ where
         let id2 = self.get_canon_id(v2);
         v1 == v2 || id1.is_some() && id1 == id2
     }
+    pub fn get_unique_id(&self, v: V) -> Option<usize> {
+        // Dense integer ID of the original (unmerged) vertex.
+        // Useful for indexing data structures that only work over usize
+        // (e.g. euler_forest.rs).
+        self.vertex_ids.get(&v).map(|id| id.0)
+    }
     pub fn get_canon_vertex(&self, v: V) -> V {
         match self.get_canon_id(v) {
             Some(CanonicalID(id)) => {
@@ -124,6 +157,16 @@ where
         // Includes every original vertex even when merged
         self.vertex_ids.keys().copied()
     }
+    pub fn iter_merged(&self, v: V) -> impl Iterator<Item = V> + '_ {
+        // All vertices that have been merged with v, including v itself
+        debug_assert!(self.is_seen(v));
+        let start = self.vertex_ids[&v];
+        iter::successors(Some(start), move |id| {
+            Some(self.id_next[id]).filter(|&next| next != start)
+        })
+        .inspect(move |_| self.time.inc())
+        .map(move |id| self.id_vertices[&id])
+    }
     pub fn iter_fwd_edges(&self, v: V) -> impl Iterator<Item = V> + '_ {
         // Note that when vertices are merged, edges aren't. So the same vertex
         // could appear more than once in the iterator; but iter_edges enforces
@@ -138,6 +181,31 @@ where
         debug_assert!(self.is_seen(v));
         self.iter_edges(v, &self.bck_edges)
     }
+    pub fn iter_bck_edges_orig(&self) -> impl Iterator<Item = (V, V)> + '_ {
+        // All edges v1 -> v2 that have a bck-edge, between the original
+        // vertices they were added for (even if v1 or v2 were merged since)
+        self.bck_edges
+            .values()
+            .flatten()
+            .inspect(move |_| self.time.inc())
+            .map(move |e| (self.id_vertices[&e.far], self.id_vertices[&e.near]))
+    }
+    pub fn iter_bck_edges_orig_from(
+        &self,
+        v: V,
+    ) -> impl Iterator<Item = V> + '_ {
+        // As iter_bck_edges_orig, but only the edges v -> w from the
+        // original vertex v, returning each w.
+        // Precondition: v is seen, and enable_orig_index was called
+        let index = self
+            .bck_edges_orig_from
+            .as_ref()
+            .expect("index of original edges is not enabled");
+        index[self.vertex_ids[&v].0]
+            .iter()
+            .inspect(move |_| self.time.inc())
+            .map(move |id| self.id_vertices[id])
+    }
     pub fn merge_using<F>(&mut self, v1: V, v2: V, merge_fun: F)
     where
         F: Fn(T, T) -> T,
@@ -152,6 +220,11 @@ where
         let canon2 = self.get_canon_id_unwrapped(v2);
         if canon1 != canon2 {
             self.id_find.union(canon1.0, canon2.0);
+            // Splice together the circular lists of merged vertices
+            let id1 = UniqueID(canon1.0);
+            let id2 = UniqueID(canon2.0);
+            let next1 = self.id_next.insert(id1, self.id_next[&id2]).unwrap();
+            self.id_next.insert(id2, next1);
             let new = CanonicalID(self.id_find.find(canon1.0));
             debug_assert_eq!(new.0, self.id_find.find(canon2.0));
             debug_assert!(new == canon1 || new == canon2);
@@ -165,9 +238,39 @@ where
             let mut old_bck = self.bck_edges.remove(&old).unwrap();
             self.fwd_edges.get_mut(&new).unwrap().append(&mut old_fwd);
             self.bck_edges.get_mut(&new).unwrap().append(&mut old_bck);
+            if let Some(log) = &mut self.merge_log {
+                log.push((v1, v2));
+            }
         }
         // Could return new vertex here; for now we return nothing.
     }
+    pub fn enable_orig_index(&mut self) {
+        // Start keeping the index for iter_bck_edges_orig_from, including
+        // the edges added so far
+        if self.bck_edges_orig_from.is_some() {
+            return;
+        }
+        let mut index = vec![Vec::new(); self.vertex_ids.len()];
+        for e in self.bck_edges.values().flatten() {
+            index[e.far.0].push(e.near);
+            self.time.inc();
+            self.space.inc();
+        }
+        self.bck_edges_orig_from = Some(index);
+    }
+    pub fn has_orig_index(&self) -> bool {
+        self.bck_edges_orig_from.is_some()
+    }
+    pub fn enable_merge_log(&mut self) {
+        // Record each merge_using call that merges two different vertices
+        if self.merge_log.is_none() {
+            self.merge_log = Some(Vec::new());
+        }
+    }
+    pub fn drain_merges(&mut self) -> Vec<(V, V)> {
+        // The pairs passed to merge_using since the last call, in order
+        self.merge_log.as_mut().map(mem::take).unwrap_or_default()
+    }
     pub fn merge(&mut self, v1: V, v2: V) {
         // Panics if v1 or v2 aren't seen, or if their labels differ
         debug_assert_eq!(self.get_label(v1), self.get_label(v2));
@@ -176,6 +279,63 @@ where
             t1
         });
     }
+    pub fn remove_edge_fwd(&mut self, v1: V, v2: V) {
+        // Remove all fwd-edges v1 -> v2. If either vertex was merged, this
+        // removes all fwd-edges between the merged vertices.
+        // Precondition: v1 and v2 are seen
+        debug_assert!(self.is_seen(v1));
+        debug_assert!(self.is_seen(v2));
+        let canon1 = self.get_canon_id_unwrapped(v1);
+        let canon2 = self.get_canon_id_unwrapped(v2);
+        let edges = self.fwd_edges.get_mut(&canon1).unwrap();
+        Self::remove_edges_core(&self.id_find, &self.time, edges, canon2);
+    }
+    pub fn remove_edge_bck(&mut self, v1: V, v2: V) {
+        // Remove all bck-edges v2 -> v1 corresponding to fwd-edge v1 -> v2
+        // Precondition: v1 and v2 are seen
+        debug_assert!(self.is_seen(v1));
+        debug_assert!(self.is_seen(v2));
+        let canon1 = self.get_canon_id_unwrapped(v1);
+        let canon2 = self.get_canon_id_unwrapped(v2);
+        let edges = self.bck_edges.get_mut(&canon2).unwrap();
+        let id_find = &self.id_find;
+        let (removed, kept): (LinkedList<Edge>, LinkedList<Edge>) =
+            mem::take(edges)
+                .into_iter()
+                .inspect(|_| self.time.inc())
+                .partition(|e| id_find.find(e.far.0) == canon1.0);
+        *edges = kept;
+        for e in removed {
+            self.remove_orig_from(e);
+        }
+    }
+    pub fn remove_edge_bck_orig(&mut self, v1: V, v2: V) {
+        // Remove the bck-edges v2 -> v1 that were added for exactly the
+        // original vertices v1 and v2. Unlike remove_edge_bck, this keeps
+        // the bck-edges for other transitions between the merged vertices.
+        // Precondition: v1 and v2 are seen
+        debug_assert!(self.is_seen(v1));
+        debug_assert!(self.is_seen(v2));
+        let removed =
+            Edge { near: self.vertex_ids[&v2], far: self.vertex_ids[&v1] };
+        let canon2 = self.get_canon_id_unwrapped(v2);
+        let edges = self.bck_edges.get_mut(&canon2).unwrap();
+        let time = &self.time;
+        let before = edges.len();
+        *edges = mem::take(edges)
+            .into_iter()
+            .inspect(|_| time.inc())
+            .filter(|&e| e != removed)
+            .collect();
+        for _ in edges.len()..before {
+            self.remove_orig_from(removed);
+        }
+    }
+    pub fn remove_edge(&mut self, v1: V, v2: V) {
+        // Remove both the fwd-edges and bck-edges for v1 -> v2
+        self.remove_edge_fwd(v1, v2);
+        self.remove_edge_bck(v1, v2);
+    }
     pub fn dfs_fwd<'a>(
         &'a self,
         source: V,
@@ -260,14 +420,20 @@ where
         debug_assert_eq!(self.id_find.find(new_id), new_id);
         debug_assert!(!self.vertex_ids.contains_key(&v));
         debug_assert!(!self.id_vertices.contains_key(&unique_id));
+        debug_assert!(!self.id_next.contains_key(&unique_id));
         debug_assert!(!self.labels.contains_key(&canon_id));
         debug_assert!(!self.fwd_edges.contains_key(&canon_id));
         debug_assert!(!self.bck_edges.contains_key(&canon_id));
         self.vertex_ids.insert(v, unique_id);
         self.id_vertices.insert(unique_id, v);
+        self.id_next.insert(unique_id, unique_id);
         self.labels.insert(canon_id, label);
         self.fwd_edges.insert(canon_id, LinkedList::new());
         self.bck_edges.insert(canon_id, LinkedList::new());
+        if let Some(index) = &mut self.bck_edges_orig_from {
+            debug_assert_eq!(index.len(), new_id);
+            index.push(Vec::new());
+        }
         self.time.inc();
         self.space.inc();
     }
@@ -279,10 +445,12 @@ where
         let canon1 = self.get_canon_id_unwrapped(v1);
         let canon2 = self.get_canon_id_unwrapped(v2);
         if canon1 != canon2 {
+            let near = self.vertex_ids[&v1];
+            let far = self.vertex_ids[&v2];
             self.fwd_edges
                 .get_mut(&canon1)
                 .unwrap()
-                .push_back(UniqueID(canon2.0));
+                .push_back(Edge { near, far });
             self.space.inc();
         }
         self.time.inc();
@@ -295,14 +463,44 @@ where
         let canon1 = self.get_canon_id_unwrapped(v1);
         let canon2 = self.get_canon_id_unwrapped(v2);
         if canon1 != canon2 {
+            let near = self.vertex_ids[&v2];
+            let far = self.vertex_ids[&v1];
             self.bck_edges
                 .get_mut(&canon2)
                 .unwrap()
-                .push_back(UniqueID(canon1.0));
+                .push_back(Edge { near, far });
+            if let Some(index) = &mut self.bck_edges_orig_from {
+                index[far.0].push(near);
+                self.space.inc();
+            }
             self.space.inc();
         }
         self.time.inc();
     }
+    fn remove_orig_from(&mut self, e: Edge) {
+        // Remove one copy of the bck-edge e from bck_edges_orig_from
+        if let Some(index) = &mut self.bck_edges_orig_from {
+            let targets = &mut index[e.far.0];
+            let i = targets.iter().position(|&id| id == e.near).unwrap();
+            targets.swap_remove(i);
+            self.time.inc();
+        }
+    }
+    fn remove_edges_core(
+        id_find: &UnionFind<usize>,
+        time: &DebugCounter,
+        edges: &mut LinkedList<Edge>,
+        target: CanonicalID,
+    ) {
+        // Associated function rather than a method so that the caller can
+        // borrow the edge list mutably at the same time.
+        // LinkedList::retain isn't stable yet, so rebuild the list instead.
+        *edges = mem::take(edges)
+            .into_iter()
+            .inspect(|_| time.inc())
+            .filter(|e| id_find.find(e.far.0) != target.0)
+            .collect();
+    }
     fn get_canon_id(&self, v: V) -> Option<CanonicalID> {
         self.vertex_ids
             .get(&v)
@@ -316,14 +514,14 @@ where
     fn iter_edges<'a>(
         &'a self,
         v: V,
-        edges: &'a HashMap<CanonicalID, LinkedList<UniqueID>>,
+        edges: &'a HashMap<CanonicalID, LinkedList<Edge>>,
     ) -> impl Iterator<Item = V> + 'a {
         self.time.inc();
         let canon = self.get_canon_id_unwrapped(v);
         edges[&canon]
             .iter()
             .inspect(move |_| self.time.inc())
-            .map(move |id| self.id_find.find(id.0))
+            .map(move |e| self.id_find.find(e.far.0))
             .filter(move |&id| id != canon.0)
             .map(move |id| self.id_vertices.get(&UniqueID(id)).unwrap())
             .copied()
@@ -59,7 +59,7 @@ impl<K: Clone + Hash + Eq, V> Hashy<K, V> for HashMap<K, V> {
     where
         V: Default,
     {
-        self.entry(k).or_insert_with(Default::default);
+        self.entry(k).or_default();
     }
     fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = (K, &'a V)> + 'a> {
         Box::new(self.iter().map(|(k, v)| (k.clone(), v)))
@@ -73,7 +73,7 @@ impl<K: Clone + Hash + Eq, V> Hashy<K, V> for HashMap<K, V> {
     This implementation is not heavily tested as it can't be used
     for avl_forest.rs.
 */
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VecMap1D<V>(Vec<Option<V>>);
 impl<V> Default for VecMap1D<V> {
     fn default() -> Self {
@@ -120,7 +120,7 @@ impl<V: Clone> Hashy<usize, V> for VecMap1D<V> {
     not necessarily contiguous memory regions)
     and crashes when indices get too large.
 */
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VecMap2D<V>(Vec<Vec<V>>);
 const VECMAP_INIT_LEN: usize = 1000;
 impl<V: Clone + Default> Default for VecMap2D<V> {
@@ -172,7 +172,7 @@ fn undo_pair(k: usize) -> (usize, usize) {
     (k - t, w + t - k)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VecMapP<V>(Vec<V>);
 impl<V: Default> Default for VecMapP<V> {
     fn default() -> Self {
@@ -230,7 +230,7 @@ impl<V: Clone + Default> Hashy<(usize, usize), V> for VecMapP<V> {
     This is the only implementation so far that manages to compete with just
     plain HashMap, and might be better. So let's use it for now.
 */
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VecMapHy<V>(Vec<HashMap<usize, V>>);
 impl<V: Clone + Default> Default for VecMapHy<V> {
     fn default() -> Self {
@@ -253,7 +253,7 @@ impl<V: Clone + Default> Hashy<(usize, usize), V> for VecMapHy<V> {
             // double size
             self.0.resize_with(2 * self.0.len(), HashMap::new);
         }
-        self.0[i].entry(j).or_insert_with(Default::default);
+        self.0[i].entry(j).or_default();
     }
     fn iter<'a>(
         &'a self,
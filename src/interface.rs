@@ -10,32 +10,71 @@
 */
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/*
+    Vertex names
+
+    States can be named by any type that is cheap to copy and can be hashed,
+    e.g. usize (the default), or tuples for product-automaton states.
+    Structured states that are not Copy (e.g. derivative terms) should be
+    interned to a Copy handle first.
+*/
+
+pub trait Vertex: Copy + Debug + Eq + Hash {}
+impl<V: Copy + Debug + Eq + Hash> Vertex for V {}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
 pub enum Status {
     Live,
     Dead,
     Unknown,
+    #[default]
     Open,
 }
-impl Default for Status {
-    fn default() -> Self {
-        Status::Open
+impl Status {
+    // Name of the status, as shown by the frontends (ffi.rs, python.rs,
+    // server.rs)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Status::Live => "Live",
+            Status::Dead => "Dead",
+            Status::Unknown => "Unknown",
+            Status::Open => "Open",
+        }
+    }
+}
+impl Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
+// A change in the status of a state, as reported by drain_events
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StatusEvent<V = usize> {
+    pub vertex: V,
+    pub old: Status,
+    pub new: Status,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum Transaction {
-    Add(usize, usize),
-    Close(usize),
-    Live(usize),
+pub enum Transaction<V = usize> {
+    Add(V, V),
+    Close(V),
+    Live(V),
+    Remove(V, V),
 }
 
 /*
     The main interface
 */
 
-pub trait StateGraph {
+pub trait StateGraph<V: Vertex = usize> {
     /*
         Functions that need to be implemented.
 
@@ -44,6 +83,8 @@ pub trait StateGraph {
           and that the source is Open.
         - mark_closed_unchecked can assume that its vertex is Open.
         - mark_live_unchecked can assume that its vertex is Open.
+        - remove_transition_unchecked can assume both its vertices are
+          distinct and seen.
 
         Derived checked versions are then provided as safer wrappers around
         these.
@@ -56,18 +97,116 @@ pub trait StateGraph {
 
     // Add a new transition to the graph from an Open state to any state.
     // (If the vertex doesn't exist yet, create it and mark it open.)
-    fn add_transition_unchecked(&mut self, v1: usize, v2: usize);
+    fn add_transition_unchecked(&mut self, v1: V, v2: V);
 
     // Mark an open state as closed.
-    fn mark_closed_unchecked(&mut self, v: usize);
+    fn mark_closed_unchecked(&mut self, v: V);
 
     // Mark an open state as live.
-    fn mark_live_unchecked(&mut self, v: usize);
+    fn mark_live_unchecked(&mut self, v: V);
+
+    // Remove all transitions from v1 to v2, where both vertices are seen and
+    // distinct, and recalculate the status of each state as if the
+    // transition had never been added.
+    // This is optional: most algorithms assume the graph only grows, so the
+    // default implementation panics.
+    fn remove_transition_unchecked(&mut self, v1: V, v2: V) {
+        panic!(
+            "remove_transition is not supported by this algorithm \
+            (tried to remove: {:?} -> {:?})",
+            v1, v2
+        );
+    }
+
+    // Mark several distinct open states as closed at once.
+    // The default just closes them one at a time; implementations can
+    // override this to resolve dead states in one combined pass.
+    fn mark_closed_batch_unchecked(&mut self, vs: &[V]) {
+        for &v in vs {
+            self.mark_closed_unchecked(v);
+        }
+    }
+
+    // Checkpoints for backtracking: pop restores the graph to the state it
+    // was in at the matching push, undoing every transaction since then.
+    // Checkpoints can be nested. Panics on pop without a matching push.
+    // (The current implementations save a full snapshot on push, see
+    // checkpoint.rs, so push takes time linear in the size of the graph.
+    // Debug statistics are rolled back as well.)
+    // This is optional, and the default implementation panics.
+    fn push(&mut self) {
+        panic!("push/pop checkpoints are not supported by this algorithm");
+    }
+    fn pop(&mut self) {
+        panic!("push/pop checkpoints are not supported by this algorithm");
+    }
+
+    // Status change events: once enabled, drain_events returns every state
+    // whose status changed since the last call, with its old and new status.
+    // Changes are collected between calls, so a state that changes and then
+    // changes back (e.g. temporarily reopened inside a transaction) is not
+    // reported. States that have not been seen count as Open.
+    // Also once enabled, drain_merges returns the pairs of states that were
+    // merged into a single vertex since the last call (see merge_using in
+    // graph.rs), in order. This is empty for algorithms that don't merge.
+    // Events are optional: by default, enable_events panics (so nothing is
+    // ever drained), and drain_merges is always empty.
+    fn enable_events(&mut self) {
+        panic!("status events are not supported by this algorithm");
+    }
+    fn drain_events(&mut self) -> Vec<StatusEvent<V>> {
+        Vec::new()
+    }
+    fn drain_merges(&mut self) -> Vec<(V, V)> {
+        Vec::new()
+    }
+
+    // Certificates (see certificate.rs): if v is Dead, the states reachable
+    // from v (including v), none of which are Open or Live; if v is Live, a
+    // path of transitions from v to a state that was marked live.
+    // Return None if v does not have that status. Both take time linear in
+    // the part of the graph reachable from v.
+    // They need an index of the transitions from each state, which costs
+    // time and space on every transition, so it must be built first with
+    // enable_certificates (then it is kept up to date); call it before any
+    // push, since pop restores the graph without it.
+    // This is optional, and the default implementations panic.
+    fn enable_certificates(&mut self) {
+        panic!("certificates are not supported by this algorithm");
+    }
+    fn dead_certificate(&self, v: V) -> Option<Vec<V>> {
+        panic!(
+            "certificates are not supported by this algorithm \
+            (tried to certify: {:?})",
+            v
+        );
+    }
+    fn live_certificate(&self, v: V) -> Option<Vec<V>> {
+        panic!(
+            "certificates are not supported by this algorithm \
+            (tried to certify: {:?})",
+            v
+        );
+    }
+
+    // Check the internal invariants of the data structure (see
+    // invariants.rs), and return a description of the first one that is
+    // violated. Slow, for debugging only; the default checks nothing.
+    fn check_invariants(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Draw the current state in Graphviz DOT format (see dot.rs), with the
+    // algorithm-specific structure on top. For debugging only.
+    // This is optional, and the default implementation panics.
+    fn to_dot(&self) -> String {
+        panic!("DOT export is not supported by this algorithm");
+    }
 
     // Return whether v is Open, or v is Closed but there is a path from
     // v to an Open state (Unknown), or there is no such path (Dead).
     // If the state is not seen, return None.
-    fn get_status(&self, v: usize) -> Option<Status>;
+    fn get_status(&self, v: V) -> Option<Status>;
 
     // Statistics -- only work in debug mode
     // space should be true memory, up to a constant, and time should be true
@@ -86,45 +225,71 @@ pub trait StateGraph {
     // inputs is correct.
     // They also remove redundant additions that don't do anything, such
     // as a self-loop edge or marking a live state closed.
-    fn add_transition(&mut self, v1: usize, v2: usize) {
-        debug_assert!(self.is_open(v1) || self.is_live(v1));
+    fn add_transition(&mut self, v1: V, v2: V) {
+        debug_assert!(self.can_process(Transaction::Add(v1, v2)));
         if self.is_open(v1) && v1 != v2 {
             self.add_transition_unchecked(v1, v2);
         }
     }
-    fn mark_closed(&mut self, v: usize) {
-        debug_assert!(self.is_open(v) || self.is_live(v));
+    fn mark_closed(&mut self, v: V) {
+        debug_assert!(self.can_process(Transaction::Close(v)));
         if self.is_open(v) {
             self.mark_closed_unchecked(v);
         }
     }
-    fn mark_live(&mut self, v: usize) {
-        debug_assert!(self.is_open(v) || self.is_live(v));
+    fn mark_live(&mut self, v: V) {
+        debug_assert!(self.can_process(Transaction::Live(v)));
         if self.is_open(v) {
             self.mark_live_unchecked(v);
         }
     }
 
+    fn mark_closed_batch(&mut self, vs: &[V]) {
+        // Duplicates and Live states are handled as in mark_closed
+        let mut seen = HashSet::new();
+        let (open, other): (Vec<V>, Vec<V>) = vs
+            .iter()
+            .copied()
+            .filter(|&v| seen.insert(v))
+            .partition(|&v| self.is_open(v));
+        for v in other {
+            self.mark_closed(v);
+        }
+        if !open.is_empty() {
+            self.mark_closed_batch_unchecked(&open);
+        }
+    }
+
+    fn remove_transition(&mut self, v1: V, v2: V) {
+        // Note: implementations supporting remove_transition should override
+        // the above checked functions to keep track of transitions and
+        // closures that are skipped for Live states, since after a removal
+        // those states may no longer be Live.
+        if self.is_seen(v1) && self.is_seen(v2) && v1 != v2 {
+            self.remove_transition_unchecked(v1, v2);
+        }
+    }
+
     // Some conveniences
-    fn is_seen(&self, v: usize) -> bool {
+    fn is_seen(&self, v: V) -> bool {
         self.get_status(v).is_some()
     }
-    fn is_live(&self, v: usize) -> bool {
+    fn is_live(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Live)
     }
-    fn is_dead(&self, v: usize) -> bool {
+    fn is_dead(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Dead)
     }
-    fn is_unknown(&self, v: usize) -> bool {
+    fn is_unknown(&self, v: V) -> bool {
         self.get_status(v) == Some(Status::Unknown)
     }
-    fn is_open(&self, v: usize) -> bool {
-        self.get_status(v).map_or(true, |st| st == Status::Open)
+    fn is_open(&self, v: V) -> bool {
+        self.get_status(v).is_none_or(|st| st == Status::Open)
     }
-    fn is_closed(&self, v: usize) -> bool {
+    fn is_closed(&self, v: V) -> bool {
         !self.is_open(v)
     }
-    fn is_live_bck(&self, v: usize) -> bool {
+    fn is_live_bck(&self, v: V) -> bool {
         // Helper function that happens to be useful going backwards to
         // check for live states
         // Return true if an unknown, live, or open state is live
@@ -132,16 +297,61 @@ pub trait StateGraph {
         debug_assert!(!self.is_dead(v));
         self.is_live(v)
     }
-    fn is_u_or_d(&self, v: usize) -> bool {
+    fn is_u_or_d(&self, v: V) -> bool {
         self.is_dead(v) || self.is_unknown(v)
     }
 
+    // Whether t is valid input to the checked functions above: the source
+    // of an Add, Close or Live must be Open or Live (so not yet known to be
+    // Dead or Unknown). Remove is always valid.
+    // Frontends taking untrusted input should check this before process.
+    fn can_process(&self, t: Transaction<V>) -> bool {
+        match t {
+            Transaction::Add(v, _)
+            | Transaction::Close(v)
+            | Transaction::Live(v) => self.is_open(v) || self.is_live(v),
+            Transaction::Remove(_, _) => true,
+        }
+    }
+
     // Same as the above but using the Transaction enum
-    fn process(&mut self, t: Transaction) {
+    fn process(&mut self, t: Transaction<V>) {
         match t {
             Transaction::Add(v1, v2) => self.add_transition(v1, v2),
             Transaction::Close(v1) => self.mark_closed(v1),
             Transaction::Live(v1) => self.mark_live(v1),
+            Transaction::Remove(v1, v2) => self.remove_transition(v1, v2),
+        }
+    }
+
+    // Process a batch of transactions, with the same result as processing
+    // them one at a time. Closures are collected and then resolved together
+    // (see mark_closed_batch_unchecked), until a transaction that depends
+    // on them: Live, Remove, or another transaction from a state that was
+    // closed in the batch.
+    fn process_batch(&mut self, batch: &[Transaction<V>]) {
+        let mut closed = Vec::new();
+        let mut closed_set = HashSet::new();
+        for &t in batch {
+            let depends = match t {
+                Transaction::Add(v1, _) | Transaction::Close(v1) => {
+                    closed_set.contains(&v1)
+                }
+                Transaction::Live(_) | Transaction::Remove(_, _) => true,
+            };
+            if depends && !closed.is_empty() {
+                self.mark_closed_batch(&closed);
+                closed.clear();
+                closed_set.clear();
+            }
+            match t {
+                Transaction::Close(v1) => {
+                    closed.push(v1);
+                    closed_set.insert(v1);
+                }
+                _ => self.process(t),
+            }
         }
+        self.mark_closed_batch(&closed);
     }
 }
@@ -0,0 +1,147 @@
+/*
+    Invariants: internal consistency checks
+
+    Helpers used by each algorithm to implement check_invariants in the
+    StateGraph interface. Checking is opt-in and slow (linear time or worse
+    per call), and is meant for debugging: driver::run_checked calls it
+    after every transaction, so that corruption is caught at the step where
+    it happens, rather than as a wrong output at the end.
+
+    Shared by all algorithms:
+    - Statuses are consistent along each transition v1 -> v2 that has a
+      bck-edge (as in certificate.rs): if v1 is Dead, so is v2, and if v2
+      is Live, so is v1.
+    - Every Unknown or Dead state was marked closed, in the algorithms that
+      keep track of this.
+    - Closed states that are not Dead do not form cycles, in the algorithms
+      that merge cycles into a single vertex as soon as they are closed.
+    The algorithm-specific invariants are checked in each algorithm.
+*/
+
+use super::graph::DiGraph;
+use super::interface::{Status, Vertex};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+pub fn check_statuses<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    status: impl Fn(&T) -> Status,
+) -> Result<(), String> {
+    let get = |v| graph.get_label(v).map(&status).unwrap();
+    for (v1, v2) in graph.iter_bck_edges_orig() {
+        let (st1, st2) = (get(v1), get(v2));
+        if st1 == Status::Dead && st2 != Status::Dead {
+            return Err(format!(
+                "{:?} is Dead but has a transition to {:?}, which is {:?}",
+                v1, v2, st2
+            ));
+        }
+        if st2 == Status::Live && st1 != Status::Live {
+            return Err(format!(
+                "{:?} is {:?} but has a transition to {:?}, which is Live",
+                v1, st1, v2
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn check_closed<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    status: impl Fn(&T) -> Status,
+    closed: &HashSet<V>,
+) -> Result<(), String> {
+    // Including each of the states that were merged
+    for v in graph.iter_vertices_all() {
+        let st = graph.get_label(v).map(&status).unwrap();
+        let is_closed = st == Status::Unknown || st == Status::Dead;
+        if is_closed && !closed.contains(&v) {
+            return Err(format!("{:?} is {:?} but was not closed", v, st));
+        }
+    }
+    Ok(())
+}
+
+// Check that there are no cycles of fwd-edges between the (merged)
+// vertices that satisfy include
+pub fn check_acyclic<V: Vertex, T: Debug + PartialEq>(
+    graph: &DiGraph<V, T>,
+    include: impl Fn(V) -> bool,
+) -> Result<(), String> {
+    // Iterative DFS; a vertex is on the stack while it is in progress
+    let mut done: HashMap<V, bool> = HashMap::new();
+    for root in graph.iter_vertices().filter(|&v| include(v)) {
+        if done.contains_key(&root) {
+            continue;
+        }
+        done.insert(root, false);
+        let mut stack = vec![(root, graph.iter_fwd_edges(root))];
+        while let Some((v, edges)) = stack.last_mut() {
+            match edges.find(|&w| include(w)) {
+                Some(w) => match done.get(&w) {
+                    Some(false) => {
+                        return Err(format!(
+                            "Cycle through {:?} and {:?} was not merged",
+                            v, w
+                        ))
+                    }
+                    Some(true) => (),
+                    None => {
+                        done.insert(w, false);
+                        stack.push((w, graph.iter_fwd_edges(w)));
+                    }
+                },
+                None => {
+                    done.insert(*v, true);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_statuses() {
+        let mut graph: DiGraph<usize, Status> = DiGraph::new();
+        graph.ensure_edge(0, 1);
+        graph.ensure_edge(1, 2);
+        assert_eq!(check_statuses(&graph, |&st| st), Ok(()));
+        graph.overwrite_vertex(1, Status::Dead);
+        let err = check_statuses(&graph, |&st| st).unwrap_err();
+        assert_eq!(err, "1 is Dead but has a transition to 2, which is Open");
+        graph.overwrite_vertex(1, Status::Unknown);
+        graph.overwrite_vertex(2, Status::Live);
+        let err = check_statuses(&graph, |&st| st).unwrap_err();
+        assert_eq!(
+            err,
+            "1 is Unknown but has a transition to 2, which is Live"
+        );
+    }
+
+    #[test]
+    fn test_check_acyclic() {
+        let mut graph: DiGraph<usize, Status> = DiGraph::new();
+        graph.ensure_edge(0, 1);
+        graph.ensure_edge(1, 2);
+        graph.ensure_edge(2, 0);
+        graph.ensure_edge(2, 3);
+        assert!(check_acyclic(&graph, |_| true).is_err());
+        // Not if the cycle is excluded, or merged
+        assert_eq!(check_acyclic(&graph, |v| v != 1), Ok(()));
+        graph.merge(0, 1);
+        graph.merge(0, 2);
+        assert_eq!(check_acyclic(&graph, |_| true), Ok(()));
+        let closed: HashSet<usize> = [0, 1].into_iter().collect();
+        graph.overwrite_vertex(0, Status::Unknown);
+        let err = check_closed(&graph, |&st| st, &closed).unwrap_err();
+        assert_eq!(err, "2 is Unknown but was not closed");
+    }
+}
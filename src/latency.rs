@@ -0,0 +1,293 @@
+/*
+    Latency of individual transactions
+
+    The total time of a run hides how long a single transaction can take,
+    which matters for interactive use (e.g. the worst-case time of one
+    Close). When enabled (see Example::run_with_progress), each transaction
+    is timed and recorded in a histogram for its kind (Add, Close, Live, or
+    Remove), from which we report the count, p50, p99, max, and mean.
+
+    Histogram buckets are log-linear: values (in nanoseconds) are grouped by
+    their highest bit, and each power of two is split into 8 linear
+    sub-buckets. So percentiles are accurate to within 1/8 (12.5%) of the
+    value, while the histogram has a fixed size and recording a value
+    doesn't allocate. The max and mean are exact.
+*/
+
+use super::driver::Algorithm;
+use super::interface::Transaction;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+const SUB_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+// Enough for any u64 value: the largest bucket index is for a value with
+// the highest bit 63, and is 61 * SUB_BUCKETS + (SUB_BUCKETS - 1)
+const NUM_BUCKETS: usize = 62 * SUB_BUCKETS;
+
+/*
+    Buckets
+*/
+
+fn bucket_of(ns: u64) -> usize {
+    if ns < SUB_BUCKETS as u64 {
+        // Small values each get their own bucket
+        return ns as usize;
+    }
+    let high = 63 - ns.leading_zeros();
+    let shift = high - SUB_BITS;
+    let sub = (ns >> shift) as usize & (SUB_BUCKETS - 1);
+    (shift as usize + 1) * SUB_BUCKETS + sub
+}
+
+// Smallest value in the bucket
+fn bucket_low(i: usize) -> u64 {
+    if i < SUB_BUCKETS {
+        return i as u64;
+    }
+    let shift = i / SUB_BUCKETS - 1;
+    let sub = i % SUB_BUCKETS;
+    ((SUB_BUCKETS + sub) as u64) << shift
+}
+
+// Largest value in the bucket
+fn bucket_high(i: usize) -> u64 {
+    if i + 1 == NUM_BUCKETS {
+        u64::MAX
+    } else {
+        bucket_low(i + 1) - 1
+    }
+}
+
+/*
+    Histogram
+*/
+
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    total_ns: u128,
+    max_ns: u64,
+}
+impl Default for Histogram {
+    fn default() -> Self {
+        Self { counts: vec![0; NUM_BUCKETS], count: 0, total_ns: 0, max_ns: 0 }
+    }
+}
+impl Histogram {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn record(&mut self, d: Duration) {
+        let ns = d.as_nanos().min(u64::MAX as u128) as u64;
+        self.counts[bucket_of(ns)] += 1;
+        self.count += 1;
+        self.total_ns += ns as u128;
+        self.max_ns = self.max_ns.max(ns);
+    }
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    pub fn max_ns(&self) -> u64 {
+        self.max_ns
+    }
+    pub fn mean_ns(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            (self.total_ns / self.count as u128) as u64
+        }
+    }
+    // Value at percentile p (between 0 and 100): the largest value in the
+    // bucket that contains it, or the max if that is smaller
+    pub fn percentile_ns(&self, p: f64) -> u64 {
+        let rank = ((p / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, &c) in self.counts.iter().enumerate() {
+            seen += c;
+            if seen >= rank {
+                return bucket_high(i).min(self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+    // The nonempty buckets, as (smallest value in ns, count)
+    pub fn buckets(&self) -> Vec<(u64, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &c)| c > 0)
+            .map(|(i, &c)| (bucket_low(i), c))
+            .collect()
+    }
+}
+
+/*
+    Histograms for each kind of transaction
+*/
+
+#[derive(Clone, Debug, Default)]
+pub struct LatencyStats {
+    add: Histogram,
+    close: Histogram,
+    live: Histogram,
+    remove: Histogram,
+}
+impl LatencyStats {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn record(&mut self, t: Transaction, d: Duration) {
+        match t {
+            Transaction::Add(_, _) => self.add.record(d),
+            Transaction::Close(_) => self.close.record(d),
+            Transaction::Live(_) => self.live.record(d),
+            Transaction::Remove(_, _) => self.remove.record(d),
+        }
+    }
+    pub fn by_kind(&self) -> [(&'static str, &Histogram); 4] {
+        [
+            ("Add", &self.add),
+            ("Close", &self.close),
+            ("Live", &self.live),
+            ("Remove", &self.remove),
+        ]
+    }
+    // Summaries for the kinds of transactions that occurred
+    pub fn summaries(&self, name: &str, alg: Algorithm) -> Vec<LatencySummary> {
+        self.by_kind()
+            .into_iter()
+            .filter(|(_, hist)| hist.count() > 0)
+            .map(|(kind, hist)| LatencySummary {
+                name: name.to_string(),
+                algorithm: alg.to_string(),
+                kind: kind.to_string(),
+                count: hist.count(),
+                p50_ns: hist.percentile_ns(50.0),
+                p99_ns: hist.percentile_ns(99.0),
+                max_ns: hist.max_ns(),
+                mean_ns: hist.mean_ns(),
+                histogram: hist.buckets(),
+            })
+            .collect()
+    }
+}
+
+/*
+    Summary for export (as CSV rows, or JSON including the histogram)
+*/
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LatencySummary {
+    pub name: String,
+    pub algorithm: String,
+    pub kind: String,
+    pub count: u64,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+    pub mean_ns: u64,
+    // Nonempty buckets, as (smallest value in ns, count)
+    pub histogram: Vec<(u64, u64)>,
+}
+impl LatencySummary {
+    pub fn csv_header() -> String {
+        "name, algorithm, kind, count, p50 (ns), p99 (ns), max (ns), \
+        mean (ns)"
+            .to_string()
+    }
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{}, {}, {}, {}, {}, {}, {}, {}",
+            self.name,
+            self.algorithm,
+            self.kind,
+            self.count,
+            self.p50_ns,
+            self.p99_ns,
+            self.max_ns,
+            self.mean_ns
+        )
+    }
+}
+impl fmt::Display for LatencySummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: count {}, p50 {}ns, p99 {}ns, max {}ns, mean {}ns",
+            self.kind,
+            self.count,
+            self.p50_ns,
+            self.p99_ns,
+            self.max_ns,
+            self.mean_ns
+        )
+    }
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        let mut prev = 0;
+        for ns in (0..10000).chain([u64::MAX / 3, u64::MAX]) {
+            let i = bucket_of(ns);
+            assert!(i < NUM_BUCKETS);
+            assert!(i >= prev);
+            prev = i;
+            // ns is in its bucket, within 1/8 of the smallest value
+            let low = bucket_low(i);
+            assert!(low <= ns && ns - low <= low / 8, "{} {}", ns, low);
+            assert!(ns <= bucket_high(i));
+        }
+        assert_eq!(bucket_of(7), 7);
+        assert_eq!(bucket_of(16), 16);
+        assert_eq!(bucket_of(31), 23);
+        assert_eq!(bucket_of(u64::MAX), NUM_BUCKETS - 1);
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut hist = Histogram::new();
+        assert_eq!(hist.percentile_ns(50.0), 0);
+        for ns in 1..=1000 {
+            hist.record(Duration::from_nanos(ns));
+        }
+        assert_eq!(hist.count(), 1000);
+        assert_eq!(hist.max_ns(), 1000);
+        assert_eq!(hist.mean_ns(), 500);
+        let p50 = hist.percentile_ns(50.0);
+        assert!((500..=500 + 500 / 8).contains(&p50), "p50: {}", p50);
+        let p99 = hist.percentile_ns(99.0);
+        assert!((990..=1000).contains(&p99), "p99: {}", p99);
+        assert_eq!(hist.percentile_ns(100.0), 1000);
+        let total: u64 = hist.buckets().iter().map(|&(_, c)| c).sum();
+        assert_eq!(total, 1000);
+    }
+
+    #[test]
+    fn test_by_kind() {
+        let mut stats = LatencyStats::new();
+        stats.record(Transaction::Add(0, 1), Duration::from_nanos(10));
+        stats.record(Transaction::Add(1, 2), Duration::from_nanos(30));
+        stats.record(Transaction::Close(0), Duration::from_micros(5));
+        let summaries = stats.summaries("ex", Algorithm::Jump);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].kind, "Add");
+        assert_eq!(summaries[0].count, 2);
+        assert_eq!(summaries[0].max_ns, 30);
+        assert_eq!(summaries[1].kind, "Close");
+        assert_eq!(summaries[1].p99_ns, 5000);
+        assert_eq!(
+            summaries[1].csv_row(),
+            "ex, jump, Close, 1, 5000, 5000, 5000, 5000"
+        );
+    }
+}
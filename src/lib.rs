@@ -1,12 +1,31 @@
 pub mod algorithm;
 pub mod avl_forest;
+pub mod bench;
+pub mod certificate;
+pub mod checkpoint;
+pub mod compact;
+pub mod concurrent;
 pub mod constants;
 pub mod debug_counter;
+pub mod dot;
 pub mod driver;
 pub mod euler_forest;
+pub mod events;
 pub mod example;
+pub mod ffi;
+pub mod fuzz;
 pub mod graph;
 pub mod hashy;
 pub mod interface;
+pub mod invariants;
+pub mod latency;
+pub mod memory;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod regression;
+pub mod report;
 pub mod search;
+pub mod server;
+pub mod stream;
+pub mod trace;
 pub mod util;
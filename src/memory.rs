@@ -0,0 +1,134 @@
+/*
+    Memory usage in release mode
+
+    In debug mode, space is counted by the algorithms themselves (see
+    debug_counter.rs), which is compiled out in release mode. Instead, a
+    binary can install TrackingAllocator as its global allocator:
+        #[global_allocator]
+        static ALLOC: TrackingAllocator = TrackingAllocator;
+    which counts the bytes allocated on each thread, so that the peak memory
+    of a run can be measured even if other runs are happening in parallel
+    on other threads (see example.rs).
+    The binaries do this with track_memory!() (below), which installs it
+    only when built with the "memory" feature. It is off by default, because
+    counting slows down allocation a little, which would affect timing
+    results.
+
+    Memory freed on a different thread than it was allocated on is
+    subtracted from the thread that frees it, so counts are only
+    meaningful as differences over a run that happens on one thread.
+*/
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Install TrackingAllocator as the global allocator if the "memory" feature
+// is enabled, and do nothing otherwise. For use once at the top level of a
+// binary.
+#[cfg(feature = "memory")]
+#[macro_export]
+macro_rules! track_memory {
+    () => {
+        #[global_allocator]
+        static ALLOC: $crate::memory::TrackingAllocator =
+            $crate::memory::TrackingAllocator;
+    };
+}
+#[cfg(not(feature = "memory"))]
+#[macro_export]
+macro_rules! track_memory {
+    () => {};
+}
+
+// Whether TrackingAllocator is installed (set on the first allocation)
+static TRACKING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Bytes currently allocated by this thread, and the peak since reset
+    static CURRENT: Cell<isize> = const { Cell::new(0) };
+    static PEAK: Cell<isize> = const { Cell::new(0) };
+}
+
+fn record(delta: isize) {
+    if !TRACKING.load(Ordering::Relaxed) {
+        TRACKING.store(true, Ordering::Relaxed);
+    }
+    // try_with: the thread locals may already be destroyed when a thread
+    // exits, in which case the allocation is not counted
+    let _ = CURRENT.try_with(|current| {
+        let new = current.get() + delta;
+        current.set(new);
+        let _ = PEAK.try_with(|peak| {
+            if new > peak.get() {
+                peak.set(new);
+            }
+        });
+    });
+}
+
+pub struct TrackingAllocator;
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record(layout.size() as isize);
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        record(-(layout.size() as isize));
+    }
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            record(new_size as isize - layout.size() as isize);
+        }
+        new_ptr
+    }
+}
+
+/*
+    Exposed API
+*/
+
+// Whether memory is being tracked, i.e. TrackingAllocator is installed
+pub fn is_tracking() -> bool {
+    TRACKING.load(Ordering::Relaxed)
+}
+
+// A measurement of the peak memory allocated by the current thread, from
+// when it is started. Measurements on the same thread shouldn't overlap.
+pub struct PeakMemory {
+    baseline: isize,
+}
+impl PeakMemory {
+    pub fn start() -> Self {
+        let baseline = CURRENT.with(|current| current.get());
+        PEAK.with(|peak| peak.set(baseline));
+        Self { baseline }
+    }
+    // Peak bytes allocated since the start, above what was allocated at
+    // the start (None if memory is not being tracked)
+    pub fn bytes(&self) -> Option<usize> {
+        if !is_tracking() {
+            return None;
+        }
+        let peak = PEAK.with(|peak| peak.get());
+        Some((peak - self.baseline).max(0) as usize)
+    }
+}
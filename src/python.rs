@@ -0,0 +1,257 @@
+/*
+    Python bindings (enabled with the "python" feature), for driving the
+    algorithms from Python prototypes and notebooks.
+
+    Build and install the extension module with maturin (see
+    pyproject.toml), e.g. `maturin develop --release`, then:
+        import guided_digraph as gid
+        g = gid.JumpStateGraph()
+        g.add(0, 1)
+        g.close(0)
+        g.status(0)       # "Unknown"
+        g.dead_states()   # []
+        gid.Example.load("examples/handwritten/15").run("jump")
+
+    Each algorithm is a class with the same methods. States are integers,
+    and statuses are the strings "Live", "Dead", "Unknown", and "Open" (as
+    in the example files). Updates to a state that is not Open or Live
+    raise ValueError.
+*/
+
+// The code generated by pyo3 for methods returning PyResult converts PyErr
+// into itself
+#![allow(clippy::useless_conversion)]
+
+use super::constants::{EXAMPLE_IN_BIN_EXT, EXAMPLE_IN_EXT};
+use super::driver::Algorithm;
+use super::example::{Example, ExampleOutput, ExampleResult};
+use super::interface::{StateGraph, Status, Transaction};
+use super::util;
+use pyo3::exceptions::{PyFileNotFoundError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::HashSet;
+use std::ops::DerefMut;
+use std::time::Duration;
+
+fn parse_algorithm(alg: &str) -> PyResult<Algorithm> {
+    alg.parse().map_err(PyValueError::new_err)
+}
+
+/*
+    State graphs
+
+    The graph itself is shared by all the classes; they only differ in the
+    algorithm used.
+*/
+
+struct Graph {
+    graph: Box<dyn StateGraph>,
+    // All states seen so far, for dead_states
+    states: HashSet<usize>,
+}
+impl Graph {
+    fn new(alg: Algorithm) -> Self {
+        Self { graph: alg.new_graph(), states: HashSet::new() }
+    }
+    fn status(&self, v: usize) -> Status {
+        self.graph.get_status(v).unwrap_or(Status::Open)
+    }
+    fn update(&mut self, v: usize, t: Transaction) -> PyResult<()> {
+        if !self.graph.can_process(t) {
+            return Err(PyValueError::new_err(format!(
+                "state {} is {}",
+                v,
+                self.status(v)
+            )));
+        }
+        self.graph.process(t);
+        match t {
+            Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
+                self.states.insert(v1);
+                self.states.insert(v2);
+            }
+            Transaction::Close(v) | Transaction::Live(v) => {
+                self.states.insert(v);
+            }
+        }
+        Ok(())
+    }
+    fn states_with(&self, st: Status) -> Vec<usize> {
+        let mut result: Vec<usize> = self
+            .states
+            .iter()
+            .copied()
+            .filter(|&v| self.status(v) == st)
+            .collect();
+        result.sort_unstable();
+        result
+    }
+}
+
+// One Python class per algorithm, with the same methods
+macro_rules! py_state_graph {
+    ($name:ident, $alg:expr) => {
+        // unsendable: the algorithms are not Send (e.g. Jump uses RefCell)
+        #[pyclass(unsendable, module = "guided_digraph")]
+        pub struct $name {
+            inner: Graph,
+        }
+        #[pymethods]
+        impl $name {
+            #[new]
+            fn new() -> Self {
+                Self { inner: Graph::new($alg) }
+            }
+            /// Add a transition from an Open or Live state
+            fn add(&mut self, v1: usize, v2: usize) -> PyResult<()> {
+                self.inner.update(v1, Transaction::Add(v1, v2))
+            }
+            /// Mark an Open or Live state as closed
+            fn close(&mut self, v: usize) -> PyResult<()> {
+                self.inner.update(v, Transaction::Close(v))
+            }
+            /// Mark an Open or Live state as live
+            fn live(&mut self, v: usize) -> PyResult<()> {
+                self.inner.update(v, Transaction::Live(v))
+            }
+            /// Status of a state ("Open" if not seen)
+            fn status(&self, v: usize) -> &'static str {
+                self.inner.status(v).as_str()
+            }
+            fn is_dead(&self, v: usize) -> bool {
+                self.inner.status(v) == Status::Dead
+            }
+            /// All dead states seen so far, sorted
+            fn dead_states(&self) -> Vec<usize> {
+                self.inner.states_with(Status::Dead)
+            }
+            /// All live states seen so far, sorted
+            fn live_states(&self) -> Vec<usize> {
+                self.inner.states_with(Status::Live)
+            }
+            fn __len__(&self) -> usize {
+                self.inner.states.len()
+            }
+            fn __repr__(&self) -> String {
+                format!(
+                    "{}({} states)",
+                    stringify!($name),
+                    self.inner.states.len()
+                )
+            }
+        }
+    };
+}
+
+py_state_graph!(NaiveStateGraph, Algorithm::Naive);
+py_state_graph!(SimpleStateGraph, Algorithm::Simple);
+py_state_graph!(BFGTStateGraph, Algorithm::BFGT);
+py_state_graph!(LogStateGraph, Algorithm::Log);
+py_state_graph!(JumpStateGraph, Algorithm::Jump);
+
+/*
+    Examples
+*/
+
+fn output_dict<'py>(
+    py: Python<'py>,
+    output: &ExampleOutput,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("live", &output.live)?;
+    dict.set_item("dead", &output.dead)?;
+    dict.set_item("unknown", &output.unknown)?;
+    dict.set_item("open", &output.open)?;
+    Ok(dict)
+}
+
+#[pyclass(name = "Example", module = "guided_digraph")]
+pub struct PyExample {
+    example: Example,
+}
+#[pymethods]
+impl PyExample {
+    /// Load an example from its basename, e.g. "examples/handwritten/15"
+    /// (reads basename_in.json or basename_in.bin, and basename_expect.json
+    /// if it exists)
+    #[staticmethod]
+    fn load(basename: &str) -> PyResult<Self> {
+        let json = format!("{}{}", basename, EXAMPLE_IN_EXT);
+        let bin = format!("{}{}", basename, EXAMPLE_IN_BIN_EXT);
+        if !util::file_exists(&json) && !util::file_exists(&bin) {
+            return Err(PyFileNotFoundError::new_err(format!(
+                "No input file for example: {}",
+                basename
+            )));
+        }
+        Ok(Self { example: Example::load_from(basename) })
+    }
+    #[getter]
+    fn name(&self) -> &str {
+        self.example.name()
+    }
+    fn __len__(&self) -> usize {
+        self.example.len()
+    }
+    /// The expected output, if there is one, as a dict of lists of states
+    fn expected<'py>(
+        &self,
+        py: Python<'py>,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.example.expected.as_ref().map(|e| output_dict(py, e)).transpose()
+    }
+    /// Run the example with an algorithm (e.g. "jump" or "j"), returning a
+    /// dict with the summary, whether the output is correct, and the output
+    #[pyo3(signature = (algorithm, timeout_secs = 60, batch_size = None))]
+    fn run<'py>(
+        &self,
+        py: Python<'py>,
+        algorithm: &str,
+        timeout_secs: u64,
+        batch_size: Option<usize>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let alg = parse_algorithm(algorithm)?;
+        let mut graph = alg.new_graph();
+        let result = self.example.run_batched_with_timeout(
+            graph.deref_mut(),
+            Duration::from_secs(timeout_secs),
+            batch_size,
+        );
+        let dict = PyDict::new_bound(py);
+        dict.set_item("algorithm", alg.to_string())?;
+        dict.set_item("summary", result.summary())?;
+        dict.set_item("timeout", result.is_timeout())?;
+        dict.set_item("correct", result.is_correct())?;
+        let completed = match result {
+            ExampleResult::Timeout(completed) => completed,
+            _ => self.example.len(),
+        };
+        dict.set_item("completed", completed)?;
+        let output = result.get_output().map(|o| output_dict(py, o));
+        dict.set_item("output", output.transpose()?)?;
+        Ok(dict)
+    }
+    fn __repr__(&self) -> String {
+        format!(
+            "Example({:?}, {} transactions)",
+            self.example.name(),
+            self.example.len()
+        )
+    }
+}
+
+/*
+    The module
+*/
+
+#[pymodule]
+fn guided_digraph(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<NaiveStateGraph>()?;
+    m.add_class::<SimpleStateGraph>()?;
+    m.add_class::<BFGTStateGraph>()?;
+    m.add_class::<LogStateGraph>()?;
+    m.add_class::<JumpStateGraph>()?;
+    m.add_class::<PyExample>()?;
+    Ok(())
+}
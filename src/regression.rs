@@ -0,0 +1,247 @@
+/*
+    Comparing two runs for regressions
+
+    Compares a new result CSV against a baseline (both in the format of
+    run_compare_csv_header, loaded as in report.rs), matching examples by
+    name and algorithms by name, and finds the changes:
+    - Slower / Faster: the time changed by more than the threshold, both
+      relatively (e.g. 0.2 for 20%) and absolutely (min_diff, to ignore
+      noise on fast examples);
+    - NewTimeout / NoLongerTimeout;
+    - NowWrong / NowCorrect: the output became wrong or correct.
+    Slower, NewTimeout, and NowWrong are regressions.
+
+    Times are in the units of the CSV files (milliseconds in release mode).
+    Examples or algorithms that are only in one of the runs are skipped.
+*/
+
+use super::report::{Outcome, ResultSet};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    NowWrong,
+    NewTimeout,
+    Slower,
+    NowCorrect,
+    NoLongerTimeout,
+    Faster,
+}
+impl ChangeKind {
+    pub fn is_regression(&self) -> bool {
+        matches!(self, Self::NowWrong | Self::NewTimeout | Self::Slower)
+    }
+}
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let result = match self {
+            Self::NowWrong => "Now wrong",
+            Self::NewTimeout => "New timeout",
+            Self::Slower => "Slower",
+            Self::NowCorrect => "Now correct",
+            Self::NoLongerTimeout => "No longer timeout",
+            Self::Faster => "Faster",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub name: String,
+    pub alg: String,
+    pub kind: ChangeKind,
+    pub before: Outcome,
+    pub after: Outcome,
+}
+impl Change {
+    pub fn is_regression(&self) -> bool {
+        self.kind.is_regression()
+    }
+}
+
+fn outcome_str(outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Time(t) => format!("{}", t),
+        Outcome::Timeout => "Timeout".to_string(),
+        Outcome::WrongOutput => "Wrong Output".to_string(),
+        Outcome::NotRun => "Not run".to_string(),
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} -> {}",
+            self.name,
+            self.alg,
+            outcome_str(self.before),
+            outcome_str(self.after)
+        )?;
+        if let (Outcome::Time(t1), Outcome::Time(t2)) =
+            (self.before, self.after)
+        {
+            write!(f, " ({:.2}x)", t2 / t1.max(1.0))?;
+        }
+        Ok(())
+    }
+}
+
+// The change from before to after, if any
+fn classify(
+    before: Outcome,
+    after: Outcome,
+    threshold: f64,
+    min_diff: f64,
+) -> Option<ChangeKind> {
+    use Outcome::*;
+    match (before, after) {
+        (NotRun, _) | (_, NotRun) => None,
+        (WrongOutput, WrongOutput) => None,
+        (_, WrongOutput) => Some(ChangeKind::NowWrong),
+        // Not known to be correct yet, so still a regression
+        (WrongOutput, Timeout) => Some(ChangeKind::NewTimeout),
+        (WrongOutput, _) => Some(ChangeKind::NowCorrect),
+        (Timeout, Timeout) => None,
+        (_, Timeout) => Some(ChangeKind::NewTimeout),
+        (Timeout, _) => Some(ChangeKind::NoLongerTimeout),
+        (Time(t1), Time(t2)) => {
+            if t2 - t1 > min_diff && t2 > t1 * (1.0 + threshold) {
+                Some(ChangeKind::Slower)
+            } else if t1 - t2 > min_diff && t1 > t2 * (1.0 + threshold) {
+                Some(ChangeKind::Faster)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// All changes from baseline to new, sorted by kind (regressions first),
+// then in the order of the examples in new
+pub fn compare(
+    baseline: &ResultSet,
+    new: &ResultSet,
+    threshold: f64,
+    min_diff: f64,
+) -> Vec<Change> {
+    let baseline_rows: HashMap<&str, usize> = baseline
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (row.name.as_str(), i))
+        .collect();
+    // Algorithms in both, as (index in baseline, index in new)
+    let algs: Vec<(usize, usize)> = new
+        .algs
+        .iter()
+        .enumerate()
+        .filter_map(|(j, alg)| {
+            Some((baseline.algs.iter().position(|a| a == alg)?, j))
+        })
+        .collect();
+    let mut changes = Vec::new();
+    for row in &new.rows {
+        let base_row = match baseline_rows.get(row.name.as_str()) {
+            Some(&i) => &baseline.rows[i],
+            None => continue,
+        };
+        for &(i, j) in &algs {
+            let before = base_row.outcomes[i];
+            let after = row.outcomes[j];
+            if let Some(kind) = classify(before, after, threshold, min_diff) {
+                changes.push(Change {
+                    name: row.name.clone(),
+                    alg: new.algs[j].clone(),
+                    kind,
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+    // Stable, so examples stay in order within each kind
+    changes.sort_by_key(|change| change.kind);
+    changes
+}
+
+// Names of examples that are only in one of the runs, as (only in baseline,
+// only in new)
+pub fn unmatched(
+    baseline: &ResultSet,
+    new: &ResultSet,
+) -> (Vec<String>, Vec<String>) {
+    let names = |results: &ResultSet| -> HashSet<String> {
+        results.rows.iter().map(|row| row.name.clone()).collect()
+    };
+    let (base_names, new_names) = (names(baseline), names(new));
+    let only_in = |results: &ResultSet, other: &HashSet<String>| {
+        results
+            .rows
+            .iter()
+            .filter(|row| !other.contains(&row.name))
+            .map(|row| row.name.clone())
+            .collect()
+    };
+    (only_in(baseline, &new_names), only_in(new, &base_names))
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        use Outcome::*;
+        let c = |before, after| classify(before, after, 0.2, 10.0);
+        assert_eq!(c(Time(100.0), Time(115.0)), None);
+        assert_eq!(c(Time(100.0), Time(130.0)), Some(ChangeKind::Slower));
+        assert_eq!(c(Time(130.0), Time(100.0)), Some(ChangeKind::Faster));
+        // Too small to count, even though it is 5x slower
+        assert_eq!(c(Time(1.0), Time(5.0)), None);
+        assert_eq!(c(Time(1.0), Timeout), Some(ChangeKind::NewTimeout));
+        assert_eq!(c(Timeout, Time(1.0)), Some(ChangeKind::NoLongerTimeout));
+        assert_eq!(c(Timeout, WrongOutput), Some(ChangeKind::NowWrong));
+        assert_eq!(c(WrongOutput, Time(1.0)), Some(ChangeKind::NowCorrect));
+        assert_eq!(c(WrongOutput, Timeout), Some(ChangeKind::NewTimeout));
+        assert_eq!(c(Timeout, Timeout), None);
+        assert_eq!(c(NotRun, Timeout), None);
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = ResultSet::from_csv(
+            "name, size, timeout, time (log), time (jump)\n\
+            a, 10, 10, 100, 100\n\
+            b, 10, 10, 100, 100\n\
+            c, 10, 10, 100, 100\n",
+        )
+        .unwrap();
+        let new = ResultSet::from_csv(
+            "name, size, timeout, time (jump), time (bfgt)\n\
+            d, 10, 10, 1, 1\n\
+            c, 10, 10, 20, 1\n\
+            b, 10, 10, Timeout, 1\n\
+            a, 10, 10, 105, 1\n",
+        )
+        .unwrap();
+        let changes = compare(&baseline, &new, 0.2, 10.0);
+        let summary: Vec<(&str, ChangeKind)> =
+            changes.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![("b", ChangeKind::NewTimeout), ("c", ChangeKind::Faster)]
+        );
+        assert!(changes[0].is_regression());
+        assert!(!changes[1].is_regression());
+        assert_eq!(changes[1].to_string(), "c (jump): 100 -> 20 (0.20x)");
+        let (only_base, only_new) = unmatched(&baseline, &new);
+        assert!(only_base.is_empty());
+        assert_eq!(only_new, vec!["d"]);
+    }
+}
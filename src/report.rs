@@ -0,0 +1,959 @@
+/*
+    Aggregating results into a report
+
+    Reads the CSV files written by run_all (or run_compare) and computes the
+    numbers summarized in the paper (Figures 4 and 5), which were previously
+    computed in a spreadsheet (see spreadsheet/):
+    - for each category of examples (generated, random, regex), the number
+      of examples, and for each algorithm the number solved and the mean and
+      median time;
+    - for each size bucket (sizes rounded on a log scale to 1, 2, or 5
+      times a power of 10), the mean time of each algorithm, and the speedup
+      of one algorithm over another (e.g. jump over bfgt);
+    - cactus plot data: for each time limit, the number of examples that
+      each algorithm solves within that limit.
+
+    As in the spreadsheet, handwritten examples (unit tests) and trivial
+    examples, where all algorithms take under 10ms, are excluded, and an
+    algorithm that times out counts as taking the whole timeout.
+    Times are in milliseconds, as in results from release mode.
+
+    The report can be rendered as Markdown or CSV tables, and as SVG plots.
+*/
+
+use super::util;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/*
+    Results loaded from CSV files
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    // Time in ms
+    Time(f64),
+    Timeout,
+    WrongOutput,
+    // Not in the results (e.g. excluded from the run)
+    NotRun,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultRow {
+    pub name: String,
+    pub size: usize,
+    pub timeout_secs: u64,
+    // One per algorithm, in the same order as ResultSet::algs
+    pub outcomes: Vec<Outcome>,
+}
+impl ResultRow {
+    pub fn category(&self) -> &str {
+        category(&self.name)
+    }
+    // Time of the algorithm in ms, counting a timeout as the whole timeout
+    // (None if the output was wrong)
+    pub fn time_ms(&self, alg: usize) -> Option<f64> {
+        match self.outcomes[alg] {
+            Outcome::Time(t) => Some(t),
+            Outcome::Timeout => Some(self.timeout_secs as f64 * 1000.0),
+            Outcome::WrongOutput | Outcome::NotRun => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ResultSet {
+    pub algs: Vec<String>,
+    pub rows: Vec<ResultRow>,
+}
+impl ResultSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // Parse the CSV format of run_compare_csv_header (see driver.rs); space
+    // columns are ignored
+    pub fn from_csv(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.is_empty());
+        let (_, header) = lines.next().ok_or("Empty CSV file")?;
+        let header: Vec<&str> = header.split(',').map(str::trim).collect();
+        if header.len() < 3 || header[..3] != ["name", "size", "timeout"] {
+            return Err(format!("Unexpected CSV header: {:?}", header));
+        }
+        // Column of each algorithm's time
+        let mut algs = Vec::new();
+        let mut columns = Vec::new();
+        for (i, col) in header.iter().enumerate() {
+            if let Some(alg) = col
+                .strip_prefix("time (")
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                algs.push(alg.to_string());
+                columns.push(i);
+            }
+        }
+        let mut rows = Vec::new();
+        for (i, line) in lines {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let err =
+                |what: &str| format!("Line {}: {}: {}", i + 1, what, line);
+            if fields.len() != header.len() {
+                return Err(err("wrong number of columns"));
+            }
+            let size = fields[1].parse().map_err(|_| err("invalid size"))?;
+            let timeout_secs =
+                fields[2].parse().map_err(|_| err("invalid timeout"))?;
+            let outcomes = columns
+                .iter()
+                .map(|&c| match fields[c] {
+                    "Timeout" => Ok(Outcome::Timeout),
+                    "Wrong Output" => Ok(Outcome::WrongOutput),
+                    t => t.parse().map(Outcome::Time),
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|_| err("invalid time"))?;
+            rows.push(ResultRow {
+                name: fields[0].to_string(),
+                size,
+                timeout_secs,
+                outcomes,
+            });
+        }
+        Ok(Self { algs, rows })
+    }
+    pub fn load_from(path: &str) -> Self {
+        Self::from_csv(&util::file_to_string(path)).unwrap_or_else(|err| {
+            panic!("Could not parse results from {:?} -- {}", path, err)
+        })
+    }
+
+    // Add the results in other, e.g. from a separate run of some algorithms.
+    // For examples in both, outcomes in other replace those in self, except
+    // for algorithms that other didn't run.
+    pub fn merge(&mut self, other: ResultSet) {
+        let alg_index: Vec<usize> = other
+            .algs
+            .into_iter()
+            .map(|alg| match self.algs.iter().position(|a| *a == alg) {
+                Some(i) => i,
+                None => {
+                    self.algs.push(alg);
+                    for row in &mut self.rows {
+                        row.outcomes.push(Outcome::NotRun);
+                    }
+                    self.algs.len() - 1
+                }
+            })
+            .collect();
+        let mut row_index: HashMap<String, usize> = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (row.name.clone(), i))
+            .collect();
+        for row in other.rows {
+            let i = *row_index.entry(row.name.clone()).or_insert_with(|| {
+                self.rows.push(ResultRow {
+                    name: row.name.clone(),
+                    size: row.size,
+                    timeout_secs: row.timeout_secs,
+                    outcomes: vec![Outcome::NotRun; self.algs.len()],
+                });
+                self.rows.len() - 1
+            });
+            let existing = &mut self.rows[i];
+            existing.size = row.size;
+            existing.timeout_secs = row.timeout_secs;
+            for (j, outcome) in row.outcomes.into_iter().enumerate() {
+                if outcome != Outcome::NotRun {
+                    existing.outcomes[alg_index[j]] = outcome;
+                }
+            }
+        }
+    }
+
+    // Rows that count towards the results: not handwritten, and not trivial
+    // (at least one algorithm takes min_time_ms or more)
+    pub fn included(&self, min_time_ms: f64) -> Vec<&ResultRow> {
+        self.rows
+            .iter()
+            .filter(|row| row.category() != "handwritten")
+            .filter(|row| {
+                (0..self.algs.len())
+                    .filter_map(|alg| row.time_ms(alg))
+                    .any(|t| t >= min_time_ms)
+            })
+            .collect()
+    }
+}
+
+// Category of an example: the directory under examples/ (e.g. "regex" for
+// "examples/regex/date/date1")
+pub fn category(name: &str) -> &str {
+    name.split('/').nth(1).unwrap_or(name)
+}
+
+// Round the size on a log scale to 1, 2, or 5 times a power of 10 (e.g.
+// sizes from about 68K to 147K are in bucket 100K)
+pub fn size_bucket(size: usize) -> usize {
+    if size == 0 {
+        return 0;
+    }
+    let step = ((size as f64).log10() * 3.0).round() as u32;
+    10usize.pow(step / 3) * (1 + (step % 3).pow(2)) as usize
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let n = sorted.len();
+    match n {
+        0 => None,
+        _ if n % 2 == 1 => Some(sorted[n / 2]),
+        _ => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2.0),
+    }
+}
+
+/*
+    The report
+*/
+
+#[derive(Clone, Debug)]
+pub struct CategoryStats {
+    pub category: String,
+    pub count: usize,
+    // One per algorithm
+    pub solved: Vec<usize>,
+    pub mean_ms: Vec<Option<f64>>,
+    pub median_ms: Vec<Option<f64>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BucketStats {
+    pub size: usize,
+    pub count: usize,
+    // One per algorithm; as in the spreadsheet, at least 1ms, so that
+    // speedups over a time of 0ms are finite
+    pub mean_ms: Vec<Option<f64>>,
+    pub speedup: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CactusPoint {
+    pub limit_ms: f64,
+    // One per algorithm: examples solved in less than limit_ms
+    pub solved: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub algs: Vec<String>,
+    // Categories in order of first appearance, then the total
+    pub categories: Vec<CategoryStats>,
+    pub buckets: Vec<BucketStats>,
+    pub cactus: Vec<CactusPoint>,
+    // Speedup of target over baseline (by name) in the size buckets
+    pub speedup: Option<(String, String)>,
+    // The included rows, for plots
+    rows: Vec<ResultRow>,
+}
+impl Report {
+    pub fn new(
+        results: &ResultSet,
+        min_time_ms: f64,
+        baseline: &str,
+        target: &str,
+    ) -> Self {
+        let rows: Vec<ResultRow> =
+            results.included(min_time_ms).into_iter().cloned().collect();
+        let algs = results.algs.clone();
+        let position = |alg| algs.iter().position(|a| a == alg);
+        let speedup_algs = position(baseline).zip(position(target));
+
+        let mut category_names: Vec<&str> = Vec::new();
+        for row in &rows {
+            if !category_names.contains(&row.category()) {
+                category_names.push(row.category());
+            }
+        }
+        let mut categories: Vec<CategoryStats> = category_names
+            .iter()
+            .map(|&c| {
+                let in_category: Vec<&ResultRow> =
+                    rows.iter().filter(|row| row.category() == c).collect();
+                category_stats(c, &algs, &in_category)
+            })
+            .collect();
+        categories.push(category_stats(
+            "total",
+            &algs,
+            &rows.iter().collect::<Vec<_>>(),
+        ));
+
+        let mut sizes: Vec<usize> =
+            rows.iter().map(|row| size_bucket(row.size)).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        let buckets = sizes
+            .into_iter()
+            .map(|size| {
+                let in_bucket: Vec<&ResultRow> = rows
+                    .iter()
+                    .filter(|row| size_bucket(row.size) == size)
+                    .collect();
+                let mean_ms: Vec<Option<f64>> = (0..algs.len())
+                    .map(|alg| {
+                        let times: Vec<f64> = in_bucket
+                            .iter()
+                            .filter_map(|row| row.time_ms(alg))
+                            .collect();
+                        mean(&times).map(|t| t.max(1.0))
+                    })
+                    .collect();
+                let speedup = speedup_algs
+                    .and_then(|(b, t)| Some(mean_ms[b]? / mean_ms[t]?));
+                BucketStats { size, count: in_bucket.len(), mean_ms, speedup }
+            })
+            .collect();
+
+        let cactus = cactus_points(&algs, &rows);
+        let speedup =
+            speedup_algs.map(|_| (baseline.to_string(), target.to_string()));
+        Self { algs, categories, buckets, cactus, speedup, rows }
+    }
+
+    // Range of speedups in the n largest size buckets
+    pub fn top_speedups(&self, n: usize) -> Option<(f64, f64)> {
+        let speedups: Vec<f64> = self
+            .buckets
+            .iter()
+            .rev()
+            .take(n)
+            .filter_map(|bucket| bucket.speedup)
+            .collect();
+        let min = speedups.iter().copied().reduce(f64::min)?;
+        let max = speedups.iter().copied().reduce(f64::max)?;
+        Some((min, max))
+    }
+}
+
+fn category_stats(
+    category: &str,
+    algs: &[String],
+    rows: &[&ResultRow],
+) -> CategoryStats {
+    let times = |alg| -> Vec<f64> {
+        rows.iter().filter_map(|row| row.time_ms(alg)).collect()
+    };
+    CategoryStats {
+        category: category.to_string(),
+        count: rows.len(),
+        solved: (0..algs.len())
+            .map(|alg| {
+                rows.iter()
+                    .filter(|row| matches!(row.outcomes[alg], Outcome::Time(_)))
+                    .count()
+            })
+            .collect(),
+        mean_ms: (0..algs.len()).map(|alg| mean(&times(alg))).collect(),
+        median_ms: (0..algs.len()).map(|alg| median(&times(alg))).collect(),
+    }
+}
+
+// Time limits are 20 per power of 10, starting at 1ms, up to the largest
+// timeout
+fn cactus_points(algs: &[String], rows: &[ResultRow]) -> Vec<CactusPoint> {
+    let max_ms = rows.iter().map(|row| row.timeout_secs).max().unwrap_or(0)
+        as f64
+        * 1000.0;
+    let mut result = Vec::new();
+    for step in 0.. {
+        let limit_ms = 10f64.powf(step as f64 / 20.0);
+        if limit_ms > max_ms {
+            break;
+        }
+        let solved = (0..algs.len())
+            .map(|alg| {
+                rows.iter()
+                    .filter(|row| match row.outcomes[alg] {
+                        Outcome::Time(t) => t < limit_ms,
+                        _ => false,
+                    })
+                    .count()
+            })
+            .collect();
+        result.push(CactusPoint { limit_ms, solved });
+    }
+    result
+}
+
+/*
+    Tables (Markdown and CSV)
+*/
+
+fn opt_str(value: Option<f64>, precision: usize) -> String {
+    match value {
+        Some(v) => format!("{:.*}", precision, v),
+        None => "--".to_string(),
+    }
+}
+
+fn md_row(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+fn md_table(header: Vec<String>, rows: Vec<Vec<String>>) -> Vec<String> {
+    let mut lines = vec![md_row(&header)];
+    lines.push(md_row(&vec!["---".to_string(); header.len()]));
+    lines.extend(rows.iter().map(|row| md_row(row)));
+    lines
+}
+
+impl Report {
+    fn alg_header(&self, first: &[&str]) -> Vec<String> {
+        let mut header: Vec<String> =
+            first.iter().map(|s| s.to_string()).collect();
+        header.extend(self.algs.iter().cloned());
+        header
+    }
+    fn category_table(
+        &self,
+        cell: impl Fn(&CategoryStats, usize) -> String,
+    ) -> Vec<String> {
+        let rows = self
+            .categories
+            .iter()
+            .map(|c| {
+                let mut row = vec![c.category.clone(), c.count.to_string()];
+                row.extend((0..self.algs.len()).map(|alg| cell(c, alg)));
+                row
+            })
+            .collect();
+        md_table(self.alg_header(&["category", "count"]), rows)
+    }
+    fn speedup_header(&self) -> Option<String> {
+        self.speedup.as_ref().map(|(baseline, target)| {
+            format!("speedup ({}/{})", baseline, target)
+        })
+    }
+
+    pub fn to_markdown(&self) -> Vec<String> {
+        let mut lines = vec!["# Results".to_string(), String::new()];
+        lines.push("## Examples solved".to_string());
+        lines.push(String::new());
+        lines.extend(self.category_table(|c, alg| {
+            format!(
+                "{} ({:.0}%)",
+                c.solved[alg],
+                100.0 * c.solved[alg] as f64 / c.count.max(1) as f64
+            )
+        }));
+        lines.push(String::new());
+        lines.push("## Mean time (ms)".to_string());
+        lines.push(String::new());
+        lines.extend(self.category_table(|c, alg| opt_str(c.mean_ms[alg], 1)));
+        lines.push(String::new());
+        lines.push("## Median time (ms)".to_string());
+        lines.push(String::new());
+        lines
+            .extend(self.category_table(|c, alg| opt_str(c.median_ms[alg], 1)));
+        lines.push(String::new());
+        lines.push("## Mean time (ms) by size".to_string());
+        lines.push(String::new());
+        let mut header = self.alg_header(&["size", "count"]);
+        header.extend(self.speedup_header());
+        let rows = self
+            .buckets
+            .iter()
+            .map(|b| {
+                let mut row = vec![b.size.to_string(), b.count.to_string()];
+                row.extend(b.mean_ms.iter().map(|&t| opt_str(t, 1)));
+                if self.speedup.is_some() {
+                    row.push(opt_str(b.speedup, 1) + "x");
+                }
+                row
+            })
+            .collect();
+        lines.extend(md_table(header, rows));
+        if let (Some((baseline, target)), Some((min, max))) =
+            (&self.speedup, self.top_speedups(5))
+        {
+            lines.push(String::new());
+            lines.push(format!(
+                "Speedup of {} over {} in the top 5 size buckets: \
+                {:.0}x to {:.0}x",
+                target, baseline, min, max
+            ));
+        }
+        lines
+    }
+
+    pub fn categories_csv(&self) -> Vec<String> {
+        let mut header = "category, count".to_string();
+        for alg in &self.algs {
+            write!(header, ", solved ({0}), mean ({0}), median ({0})", alg)
+                .unwrap();
+        }
+        let mut lines = vec![header];
+        for c in &self.categories {
+            let mut line = format!("{}, {}", c.category, c.count);
+            for alg in 0..self.algs.len() {
+                write!(
+                    line,
+                    ", {}, {}, {}",
+                    c.solved[alg],
+                    opt_str(c.mean_ms[alg], 3),
+                    opt_str(c.median_ms[alg], 3)
+                )
+                .unwrap();
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    pub fn buckets_csv(&self) -> Vec<String> {
+        let mut header = "size, count".to_string();
+        for alg in &self.algs {
+            write!(header, ", mean ({})", alg).unwrap();
+        }
+        if let Some(speedup) = self.speedup_header() {
+            write!(header, ", {}", speedup).unwrap();
+        }
+        let mut lines = vec![header];
+        for b in &self.buckets {
+            let mut line = format!("{}, {}", b.size, b.count);
+            for &t in &b.mean_ms {
+                write!(line, ", {}", opt_str(t, 3)).unwrap();
+            }
+            if self.speedup.is_some() {
+                write!(line, ", {}", opt_str(b.speedup, 3)).unwrap();
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    pub fn cactus_csv(&self) -> Vec<String> {
+        let mut header = "time limit (ms)".to_string();
+        for alg in &self.algs {
+            write!(header, ", solved ({})", alg).unwrap();
+        }
+        let mut lines = vec![header];
+        for p in &self.cactus {
+            let mut line = format!("{:.3}", p.limit_ms);
+            for n in &p.solved {
+                write!(line, ", {}", n).unwrap();
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+/*
+    Plots (SVG)
+*/
+
+const PLOT_WIDTH: f64 = 640.0;
+const PLOT_HEIGHT: f64 = 400.0;
+const PLOT_MARGIN: f64 = 60.0;
+const PLOT_COLORS: &[&str] =
+    &["#7f7f7f", "#ff7f0e", "#1f77b4", "#2ca02c", "#d62728", "#9467bd"];
+
+struct Axis {
+    min: f64,
+    max: f64,
+    log: bool,
+    label: &'static str,
+}
+impl Axis {
+    // For a log scale, from the power of 10 below min to the one above max
+    fn new(values: &[f64], log: bool, label: &'static str) -> Self {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let (min, max) = if log {
+            let min = min.max(1.0).log10().floor();
+            let max = max.max(1.0).log10().ceil().max(min + 1.0);
+            (10f64.powf(min), 10f64.powf(max))
+        } else {
+            (0.0, max.max(1.0))
+        };
+        Self { min, max, log, label }
+    }
+    // Position between 0 and 1
+    fn frac(&self, v: f64) -> f64 {
+        if self.log {
+            let v = v.max(self.min);
+            (v.log10() - self.min.log10())
+                / (self.max.log10() - self.min.log10())
+        } else {
+            (v - self.min) / (self.max - self.min)
+        }
+    }
+    fn ticks(&self) -> Vec<f64> {
+        if self.log {
+            let lo = self.min.log10().round() as i32;
+            let hi = self.max.log10().round() as i32;
+            (lo..=hi).map(|e| 10f64.powi(e)).collect()
+        } else {
+            // About 5 ticks, at a multiple of 1, 2, or 5 times a power of 10
+            let rough = self.max / 5.0;
+            let pow = 10f64.powf(rough.log10().floor());
+            let step = [1.0, 2.0, 5.0, 10.0]
+                .iter()
+                .map(|m| m * pow)
+                .find(|&s| s >= rough)
+                .unwrap_or(pow * 10.0);
+            (0..)
+                .map(|i| i as f64 * step)
+                .take_while(|&t| t <= self.max)
+                .collect()
+        }
+    }
+}
+
+fn x_pos(axis: &Axis, v: f64) -> f64 {
+    PLOT_MARGIN + axis.frac(v) * (PLOT_WIDTH - 2.0 * PLOT_MARGIN)
+}
+fn y_pos(axis: &Axis, v: f64) -> f64 {
+    PLOT_HEIGHT - PLOT_MARGIN - axis.frac(v) * (PLOT_HEIGHT - 2.0 * PLOT_MARGIN)
+}
+
+fn tick_label(v: f64) -> String {
+    if v >= 1e6 {
+        format!("{}M", v / 1e6)
+    } else if v >= 1e3 {
+        format!("{}K", v / 1e3)
+    } else {
+        format!("{}", v)
+    }
+}
+
+// A plot with one series of (x, y) points per algorithm, drawn as a line or
+// as separate points
+fn svg_plot(
+    title: &str,
+    x: &Axis,
+    y: &Axis,
+    series: &[(&str, Vec<(f64, f64)>)],
+    as_line: bool,
+) -> String {
+    let mut svg = String::new();
+    let (left, right) = (PLOT_MARGIN, PLOT_WIDTH - PLOT_MARGIN);
+    let (top, bottom) = (PLOT_MARGIN, PLOT_HEIGHT - PLOT_MARGIN);
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" \
+        height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" \
+        font-size=\"12\">",
+        PLOT_WIDTH, PLOT_HEIGHT
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n\
+        <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" font-size=\"16\">\
+        {}</text>",
+        PLOT_WIDTH / 2.0,
+        top / 2.0,
+        title
+    )
+    .unwrap();
+    // Axes, ticks, and grid lines
+    for t in x.ticks() {
+        let px = x_pos(x, t);
+        writeln!(
+            svg,
+            "<line x1=\"{0:.1}\" y1=\"{1}\" x2=\"{0:.1}\" y2=\"{2}\" \
+            stroke=\"#ddd\"/>\n\
+            <text x=\"{0:.1}\" y=\"{3}\" text-anchor=\"middle\">{4}</text>",
+            px,
+            top,
+            bottom,
+            bottom + 16.0,
+            tick_label(t)
+        )
+        .unwrap();
+    }
+    for t in y.ticks() {
+        let py = y_pos(y, t);
+        writeln!(
+            svg,
+            "<line x1=\"{0}\" y1=\"{1:.1}\" x2=\"{2}\" y2=\"{1:.1}\" \
+            stroke=\"#ddd\"/>\n\
+            <text x=\"{3}\" y=\"{4:.1}\" text-anchor=\"end\">{5}</text>",
+            left,
+            py,
+            right,
+            left - 6.0,
+            py + 4.0,
+            tick_label(t)
+        )
+        .unwrap();
+    }
+    writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" \
+        stroke=\"black\"/>\n\
+        <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n\
+        <text transform=\"translate({},{}) rotate(-90)\" \
+        text-anchor=\"middle\">{}</text>",
+        left,
+        top,
+        right - left,
+        bottom - top,
+        (left + right) / 2.0,
+        PLOT_HEIGHT - 20.0,
+        x.label,
+        20.0,
+        (top + bottom) / 2.0,
+        y.label
+    )
+    .unwrap();
+    // Data and legend
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = PLOT_COLORS[i % PLOT_COLORS.len()];
+        let coords: Vec<(f64, f64)> = points
+            .iter()
+            .map(|&(px, py)| (x_pos(x, px), y_pos(y, py)))
+            .collect();
+        if as_line {
+            let path: Vec<String> = coords
+                .iter()
+                .map(|(px, py)| format!("{:.1},{:.1}", px, py))
+                .collect();
+            writeln!(
+                svg,
+                "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" \
+                points=\"{}\"/>",
+                color,
+                path.join(" ")
+            )
+            .unwrap();
+        } else {
+            for (px, py) in coords {
+                writeln!(
+                    svg,
+                    "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2.5\" \
+                    fill=\"{}\" fill-opacity=\"0.6\"/>",
+                    px, py, color
+                )
+                .unwrap();
+            }
+        }
+        let ly = top + 10.0 + 16.0 * i as f64;
+        writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{}\"/>\n\
+            <text x=\"{}\" y=\"{}\">{}</text>",
+            left + 10.0,
+            ly - 9.0,
+            color,
+            left + 26.0,
+            ly,
+            name
+        )
+        .unwrap();
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+impl Report {
+    // Number of examples solved within each time limit (Figure 5, left)
+    pub fn cactus_svg(&self) -> String {
+        let limits: Vec<f64> = self.cactus.iter().map(|p| p.limit_ms).collect();
+        let counts: Vec<f64> = self
+            .cactus
+            .iter()
+            .flat_map(|p| p.solved.iter().map(|&n| n as f64))
+            .collect();
+        let x = Axis::new(&limits, true, "time limit (ms)");
+        let y = Axis::new(&counts, false, "examples solved");
+        let series: Vec<(&str, Vec<(f64, f64)>)> = self
+            .algs
+            .iter()
+            .enumerate()
+            .map(|(alg, name)| {
+                let points = self
+                    .cactus
+                    .iter()
+                    .map(|p| (p.limit_ms, p.solved[alg] as f64))
+                    .collect();
+                (name.as_str(), points)
+            })
+            .collect();
+        svg_plot("Examples solved", &x, &y, &series, true)
+    }
+    // Time of each algorithm on each example, by size (Figure 5, top right)
+    pub fn scatter_svg(&self) -> String {
+        let series: Vec<(&str, Vec<(f64, f64)>)> = self
+            .algs
+            .iter()
+            .enumerate()
+            .map(|(alg, name)| {
+                let points = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| {
+                        Some((row.size as f64, row.time_ms(alg)?))
+                    })
+                    .collect();
+                (name.as_str(), points)
+            })
+            .collect();
+        let all: Vec<(f64, f64)> =
+            series.iter().flat_map(|(_, points)| points.clone()).collect();
+        let sizes: Vec<f64> = all.iter().map(|p| p.0).collect();
+        let times: Vec<f64> = all.iter().map(|p| p.1).collect();
+        let x = Axis::new(&sizes, true, "example size");
+        let y = Axis::new(&times, true, "time (ms)");
+        svg_plot("Time by example size", &x, &y, &series, false)
+    }
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "\
+name, size, timeout, time (bfgt), space (bfgt), time (jump), space (jump)
+examples/handwritten/1, 10, 10, 50, 0, 40, 0
+examples/generated/a, 100, 10, 20, 0, 5, 0
+examples/generated/b, 110, 10, 3, 0, 2, 0
+examples/random/c, 1000, 10, Timeout, 0, 100, 0
+examples/random/d, 2000, 10, 4000, 0, 40, 0
+";
+
+    #[test]
+    fn test_parse() {
+        let results = ResultSet::from_csv(CSV).unwrap();
+        assert_eq!(results.algs, vec!["bfgt", "jump"]);
+        assert_eq!(results.rows.len(), 5);
+        let c = &results.rows[3];
+        assert_eq!(c.category(), "random");
+        assert_eq!(c.outcomes, vec![Outcome::Timeout, Outcome::Time(100.0)]);
+        assert_eq!(c.time_ms(0), Some(10000.0));
+        // Handwritten and trivial examples are excluded
+        let included: Vec<&str> = results
+            .included(10.0)
+            .iter()
+            .map(|row| row.name.as_str())
+            .collect();
+        assert_eq!(
+            included,
+            vec![
+                "examples/generated/a",
+                "examples/random/c",
+                "examples/random/d"
+            ]
+        );
+        let wrong = ResultSet::from_csv(
+            "name, size, timeout, time (jump)\nx, 1, 10, Wrong Output\n",
+        )
+        .unwrap();
+        assert_eq!(wrong.rows[0].outcomes, vec![Outcome::WrongOutput]);
+        assert_eq!(wrong.rows[0].time_ms(0), None);
+        assert!(ResultSet::from_csv("name, size\n").is_err());
+        assert!(ResultSet::from_csv(
+            "name, size, timeout, time (j)\n\
+                                     x, 1, 10, fast\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut results = ResultSet::from_csv(CSV).unwrap();
+        let other = ResultSet::from_csv(
+            "name, size, timeout, time (naive), time (jump)\n\
+            examples/generated/a, 100, 10, 500, 6\n\
+            examples/generated/e, 5, 10, 1, 1\n",
+        )
+        .unwrap();
+        results.merge(other);
+        assert_eq!(results.algs, vec!["bfgt", "jump", "naive"]);
+        assert_eq!(results.rows.len(), 6);
+        assert_eq!(
+            results.rows[1].outcomes,
+            vec![Outcome::Time(20.0), Outcome::Time(6.0), Outcome::Time(500.0)]
+        );
+        assert_eq!(results.rows[2].outcomes[2], Outcome::NotRun);
+        assert_eq!(results.rows[5].outcomes[0], Outcome::NotRun);
+    }
+
+    #[test]
+    fn test_size_bucket() {
+        assert_eq!(size_bucket(1), 1);
+        assert_eq!(size_bucket(5150), 5000);
+        assert_eq!(size_bucket(3310), 5000);
+        assert_eq!(size_bucket(2000), 2000);
+        assert_eq!(size_bucket(601), 500);
+        assert_eq!(size_bucket(100_000), 100_000);
+        assert_eq!(size_bucket(2_012_345), 2_000_000);
+    }
+
+    #[test]
+    fn test_report() {
+        let results = ResultSet::from_csv(CSV).unwrap();
+        let report = Report::new(&results, 10.0, "bfgt", "jump");
+        let cats: Vec<(&str, usize, Vec<usize>)> = report
+            .categories
+            .iter()
+            .map(|c| (c.category.as_str(), c.count, c.solved.clone()))
+            .collect();
+        assert_eq!(
+            cats,
+            vec![
+                ("generated", 1, vec![1, 1]),
+                ("random", 2, vec![1, 2]),
+                ("total", 3, vec![2, 3]),
+            ]
+        );
+        assert_eq!(
+            report.categories[1].mean_ms,
+            vec![Some(7000.0), Some(70.0)]
+        );
+        assert_eq!(report.categories[2].median_ms[1], Some(40.0));
+        let sizes: Vec<usize> = report.buckets.iter().map(|b| b.size).collect();
+        assert_eq!(sizes, vec![100, 1000, 2000]);
+        assert_eq!(report.buckets[1].speedup, Some(100.0));
+        assert_eq!(report.top_speedups(2), Some((100.0, 100.0)));
+        // Cactus: limits from 1ms to the timeout, 20 per power of 10
+        assert_eq!(report.cactus.len(), 81);
+        assert_eq!(report.cactus[0].solved, vec![0, 0]);
+        assert_eq!(report.cactus[80].solved, vec![2, 3]);
+        let md = report.to_markdown().join("\n");
+        assert!(md.contains("| random | 2 | 1 (50%) | 2 (100%) |"));
+        assert!(md.contains("| 1000 | 1 | 10000.0 | 100.0 | 100.0x |"));
+        assert_eq!(
+            report.buckets_csv()[0],
+            "size, count, mean (bfgt), \
+                    mean (jump), speedup (bfgt/jump)"
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let results = ResultSet::from_csv(CSV).unwrap();
+        let report = Report::new(&results, 10.0, "bfgt", "jump");
+        for svg in [report.cactus_svg(), report.scatter_svg()] {
+            assert!(svg.starts_with("<svg "));
+            assert!(svg.ends_with("</svg>"));
+            assert!(svg.contains(">jump</text>"));
+            assert!(!svg.contains("NaN"));
+        }
+        assert_eq!(report.scatter_svg().matches("<circle").count(), 6);
+    }
+}
@@ -0,0 +1,324 @@
+/*
+    A simple line protocol for using a state graph from another process
+    (see the gid_server binary).
+
+    Each line is one command, and gets exactly one line in response:
+        add V1 V2       add a transition            -> ok
+        close V         mark a state closed         -> ok
+        live V          mark a state live           -> ok
+        remove V1 V2    remove a transition         -> ok
+        push / pop      checkpoints (see interface) -> ok
+        status V        status of a state           -> live|dead|unknown|open
+        dead-set        all dead states             -> list of states
+        stats           summary of the graph        -> key-value pairs
+        help            list commands               -> list of commands
+        quit            end the session             -> bye
+    States are numbers (usize). Invalid commands, or transactions that
+    aren't allowed (e.g. adding a transition from a closed state) are
+    answered with "error: ..." and don't change the graph.
+    Blank lines and lines starting with '#' are ignored (no response).
+*/
+
+use super::driver::Algorithm;
+use super::interface::{StateGraph, Status, Transaction};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+const HELP: &str = "commands: add V1 V2, close V, live V, remove V1 V2, \
+    push, pop, status V, dead-set, stats, help, quit";
+
+// Statuses are lowercase in the protocol
+fn status_name(st: Status) -> String {
+    st.as_str().to_lowercase()
+}
+
+pub struct Server {
+    alg: Algorithm,
+    graph: Box<dyn StateGraph>,
+    // All states mentioned so far, for dead-set and stats
+    states: HashSet<usize>,
+    transactions: usize,
+    // Saved states and transactions at each push, restored on pop
+    checkpoints: Vec<(HashSet<usize>, usize)>,
+}
+impl Server {
+    pub fn new(alg: Algorithm) -> Self {
+        Self {
+            alg,
+            graph: alg.new_graph(),
+            states: HashSet::new(),
+            transactions: 0,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    // Respond to one line of input; None if the line is blank or a comment.
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            return None;
+        }
+        let response = self
+            .handle_command(&words)
+            .unwrap_or_else(|err| format!("error: {}", err));
+        Some(response)
+    }
+
+    // Serve one session: respond to each line until quit or end of input.
+    pub fn serve<R: BufRead, W: Write>(
+        &mut self,
+        input: R,
+        output: &mut W,
+    ) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if let Some(response) = self.handle(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+                if response == "bye" {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /*
+        Internal
+    */
+    fn handle_command(&mut self, words: &[&str]) -> Result<String, String> {
+        let args = &words[1..];
+        let ok = || Ok("ok".to_string());
+        match words[0] {
+            "add" => {
+                let [v1, v2] = parse_states(args)?;
+                self.update(v1, Transaction::Add(v1, v2))?;
+                ok()
+            }
+            "close" => {
+                let [v] = parse_states(args)?;
+                self.update(v, Transaction::Close(v))?;
+                ok()
+            }
+            "live" => {
+                let [v] = parse_states(args)?;
+                self.update(v, Transaction::Live(v))?;
+                ok()
+            }
+            "remove" => {
+                let [v1, v2] = parse_states(args)?;
+                self.process(Transaction::Remove(v1, v2))?;
+                ok()
+            }
+            "push" => {
+                parse_states::<0>(args)?;
+                self.graph.push();
+                self.checkpoints.push((self.states.clone(), self.transactions));
+                ok()
+            }
+            "pop" => {
+                parse_states::<0>(args)?;
+                let (states, transactions) = self
+                    .checkpoints
+                    .pop()
+                    .ok_or("pop without a matching push")?;
+                self.graph.pop();
+                self.states = states;
+                self.transactions = transactions;
+                ok()
+            }
+            "status" => {
+                let [v] = parse_states(args)?;
+                Ok(status_name(self.get_status(v)))
+            }
+            "dead-set" => {
+                parse_states::<0>(args)?;
+                let mut dead: Vec<usize> = self
+                    .states
+                    .iter()
+                    .copied()
+                    .filter(|&v| self.graph.is_dead(v))
+                    .collect();
+                dead.sort_unstable();
+                let dead: Vec<String> =
+                    dead.iter().map(|v| v.to_string()).collect();
+                Ok(dead.join(" "))
+            }
+            "stats" => {
+                parse_states::<0>(args)?;
+                Ok(self.stats())
+            }
+            "help" => Ok(HELP.to_string()),
+            "quit" | "exit" => Ok("bye".to_string()),
+            cmd => Err(format!("unknown command '{}' ({})", cmd, HELP)),
+        }
+    }
+    fn get_status(&self, v: usize) -> Status {
+        self.graph.get_status(v).unwrap_or(Status::Open)
+    }
+    // Process t, with v its source state, if it is valid (see can_process)
+    fn update(&mut self, v: usize, t: Transaction) -> Result<(), String> {
+        if !self.graph.can_process(t) {
+            let st = status_name(self.get_status(v));
+            return Err(format!("state {} is {}", v, st));
+        }
+        self.process(t)
+    }
+    fn process(&mut self, t: Transaction) -> Result<(), String> {
+        // Algorithms that don't support removal panic; report that as an
+        // error rather than ending the session
+        let graph = &mut self.graph;
+        panic::catch_unwind(AssertUnwindSafe(|| graph.process(t))).map_err(
+            |err| {
+                err.downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| {
+                        err.downcast_ref::<&str>().map(|s| s.to_string())
+                    })
+                    .unwrap_or_else(|| "transaction failed".to_string())
+            },
+        )?;
+        match t {
+            Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
+                self.states.insert(v1);
+                self.states.insert(v2);
+            }
+            Transaction::Close(v) | Transaction::Live(v) => {
+                self.states.insert(v);
+            }
+        }
+        self.transactions += 1;
+        Ok(())
+    }
+    fn stats(&self) -> String {
+        let mut counts = [0; 4];
+        for &v in &self.states {
+            counts[self.get_status(v) as usize] += 1;
+        }
+        let mut result = format!(
+            "algorithm {} transactions {} states {} \
+            live {} dead {} unknown {} open {}",
+            self.alg,
+            self.transactions,
+            self.states.len(),
+            counts[Status::Live as usize],
+            counts[Status::Dead as usize],
+            counts[Status::Unknown as usize],
+            counts[Status::Open as usize],
+        );
+        if cfg!(debug_assertions) {
+            result += &format!(
+                " time {} space {}",
+                self.graph.get_time(),
+                self.graph.get_space()
+            );
+        }
+        result
+    }
+}
+
+fn parse_states<const N: usize>(args: &[&str]) -> Result<[usize; N], String> {
+    if args.len() != N {
+        return Err(format!("expected {} state(s), got {}", N, args.len()));
+    }
+    let mut result = [0; N];
+    for (i, arg) in args.iter().enumerate() {
+        result[i] = arg
+            .parse()
+            .map_err(|_| format!("could not parse state '{}'", arg))?;
+    }
+    Ok(result)
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(alg: Algorithm, input: &str) -> Vec<String> {
+        let mut server = Server::new(alg);
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_session() {
+        let input = "add 1 2\nadd 2 3\nclose 1\n\n# comment\nstatus 1\n\
+            close 2\nclose 3\nstatus 1\ndead-set\nadd 4 5\nlive 5\nstatus 4\n\
+            status 9\nquit\nstatus 1\n";
+        for &alg in crate::driver::ALL_ALGS {
+            assert_eq!(
+                session(alg, input),
+                vec![
+                    "ok", "ok", "ok", "unknown", "ok", "ok", "dead", "1 2 3",
+                    "ok", "ok", "live", "open", "bye"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let out = session(Algorithm::Jump, "add 1 2\nclose 2\nstats\n");
+        assert!(out[2].starts_with(
+            "algorithm jump transactions 2 states 2 \
+            live 0 dead 1 unknown 0 open 1"
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        let out = session(
+            Algorithm::Jump,
+            "foo\nadd 1\nadd 1 x\nadd 3 4\nremove 3 4\nclose 1\nadd 1 2\n\
+            pop\nstatus 1\nstatus 3",
+        );
+        assert!(out[0].starts_with("error: unknown command 'foo'"));
+        assert_eq!(out[1], "error: expected 2 state(s), got 1");
+        assert_eq!(out[2], "error: could not parse state 'x'");
+        assert_eq!(out[3], "ok");
+        assert!(out[4].starts_with("error: remove_transition is not supported"));
+        assert_eq!(out[5], "ok");
+        assert_eq!(out[6], "error: state 1 is dead");
+        assert_eq!(out[7], "error: pop without a matching push");
+        assert_eq!(out[8], "dead");
+        assert_eq!(out[9], "open");
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let out = session(
+            Algorithm::Log,
+            "push\nclose 1\nstatus 1\npop\nstatus 1\nadd 1 2\n",
+        );
+        assert_eq!(out, vec!["ok", "ok", "dead", "ok", "open", "ok"]);
+    }
+
+    #[test]
+    fn test_push_pop_stats() {
+        // States and transactions since the push are rolled back as well
+        let out = session(
+            Algorithm::Jump,
+            "add 1 2
+push
+close 2
+add 1 3
+close 3
+dead-set
+pop
+            dead-set
+stats
+",
+        );
+        assert_eq!(out[5], "2 3");
+        assert_eq!(out[7], "");
+        assert!(out[8].starts_with(
+            "algorithm jump transactions 1 states 2 \
+            live 0 dead 0 unknown 0 open 2"
+        ));
+    }
+}
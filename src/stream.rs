@@ -0,0 +1,279 @@
+/*
+    Streaming input: read transactions one at a time from a file or stdin,
+    and feed them to a state graph as they arrive, without loading the
+    whole input into memory first (unlike ExampleInput).
+
+    The input is either a JSON array (the same format as the _in.json
+    example files) or JSON lines (any sequence of JSON values separated by
+    whitespace). Besides transactions, e.g. {"Add":[0,1]} or {"Close":0},
+    the input can contain status queries, {"Status":0}, which are answered
+    right away with one line of JSON, e.g. {"vertex":0,"status":"Dead"}.
+    States that have not been seen are reported as Open.
+*/
+
+use super::interface::{StateGraph, Status, Transaction};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, ErrorKind, Write};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Query {
+    Status(usize),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum StreamItem {
+    Transaction(Transaction),
+    Query(Query),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub vertex: usize,
+    pub status: Status,
+}
+
+/*
+    Reader: an iterator over the items in the input
+*/
+
+pub struct StreamReader<R> {
+    reader: R,
+    started: bool,
+    // Whether the input is a JSON array (decided by the first character)
+    is_array: bool,
+    done: bool,
+    count: usize,
+}
+impl<R: BufRead> StreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, started: false, is_array: false, done: false, count: 0 }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+    fn consume(&mut self) {
+        self.reader.consume(1);
+    }
+    fn skip_whitespace(&mut self) -> io::Result<()> {
+        while self.peek()?.is_some_and(|b| b.is_ascii_whitespace()) {
+            self.consume();
+        }
+        Ok(())
+    }
+    fn read_value(&mut self) -> io::Result<Vec<u8>> {
+        // Read the bytes of one JSON value, by matching brackets and quotes.
+        // Stops before any delimiter following the value (whitespace, a
+        // comma, or the closing bracket of the array around it).
+        let mut result = Vec::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        loop {
+            let b = match self.peek()? {
+                Some(b) => b,
+                None if depth == 0 && !in_string => break,
+                None => {
+                    return Err(error(self.count, "unexpected end of input"))
+                }
+            };
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    in_string = false;
+                }
+            } else {
+                match b {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' if depth == 0 => break,
+                    b'}' | b']' => depth -= 1,
+                    b',' if depth == 0 => break,
+                    _ if depth == 0 && b.is_ascii_whitespace() => break,
+                    _ => (),
+                }
+            }
+            result.push(b);
+            self.consume();
+        }
+        Ok(result)
+    }
+    fn next_item(&mut self) -> io::Result<Option<StreamItem>> {
+        if !self.started {
+            self.started = true;
+            self.skip_whitespace()?;
+            if self.peek()? == Some(b'[') {
+                self.consume();
+                self.is_array = true;
+            }
+        }
+        self.skip_whitespace()?;
+        if self.is_array {
+            match self.peek()? {
+                Some(b']') => {
+                    self.consume();
+                    return Ok(None);
+                }
+                Some(b',') if self.count > 0 => {
+                    self.consume();
+                    self.skip_whitespace()?;
+                }
+                None => return Err(error(self.count, "missing ']'")),
+                Some(_) if self.count > 0 => {
+                    return Err(error(self.count, "expected ',' or ']'"));
+                }
+                Some(_) => (),
+            }
+        } else if self.peek()?.is_none() {
+            return Ok(None);
+        }
+        let value = self.read_value()?;
+        let item = serde_json::from_slice(&value).map_err(|err| {
+            let value = String::from_utf8_lossy(&value);
+            error(self.count, &format!("could not parse {} -- {}", value, err))
+        })?;
+        self.count += 1;
+        Ok(Some(item))
+    }
+}
+impl<R: BufRead> Iterator for StreamReader<R> {
+    type Item = io::Result<StreamItem>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_item().transpose();
+        // Stop after the end of input or the first error
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+fn error(count: usize, msg: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("Invalid input stream at item {}: {}", count, msg),
+    )
+}
+
+/*
+    Feed a stream to a state graph
+*/
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StreamStats {
+    pub transactions: usize,
+    pub queries: usize,
+}
+
+// Process the items in order, writing the answer to each query to 'out'
+// (and flushing, so that a process on the other end of a pipe gets the
+// answer right away).
+pub fn process_stream<G, R, W>(
+    graph: &mut G,
+    reader: StreamReader<R>,
+    out: &mut W,
+) -> io::Result<StreamStats>
+where
+    G: StateGraph + ?Sized,
+    R: BufRead,
+    W: Write,
+{
+    let mut stats = StreamStats::default();
+    for item in reader {
+        match item? {
+            StreamItem::Transaction(t) => {
+                graph.process(t);
+                stats.transactions += 1;
+            }
+            StreamItem::Query(Query::Status(v)) => {
+                let status = graph.get_status(v).unwrap_or(Status::Open);
+                let result = QueryResult { vertex: v, status };
+                serde_json::to_writer(&mut *out, &result)?;
+                writeln!(out)?;
+                out.flush()?;
+                stats.queries += 1;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/*
+    Unit tests
+*/
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::JumpStateGraph;
+
+    fn read_all(input: &str) -> io::Result<Vec<StreamItem>> {
+        StreamReader::new(input.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_read_array() {
+        let expected = vec![
+            StreamItem::Transaction(Transaction::Add(0, 1)),
+            StreamItem::Query(Query::Status(1)),
+            StreamItem::Transaction(Transaction::Close(0)),
+        ];
+        let input = r#"[{"Add":[0,1]},{"Status":1},{"Close":0}]"#;
+        assert_eq!(read_all(input).unwrap(), expected);
+        let input =
+            "[\n  {\n    \"Add\": [\n      0,\n      1\n    ]\n  },\n  \
+            { \"Status\": 1 } , {\"Close\": 0}\n]\n";
+        assert_eq!(read_all(input).unwrap(), expected);
+        assert_eq!(read_all("[]").unwrap(), vec![]);
+        assert_eq!(read_all(" [ ] ").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let input = "{\"Add\":[0,1]}\n{\"Status\":1}\n\n{\"Close\":0}\n";
+        assert_eq!(
+            read_all(input).unwrap(),
+            vec![
+                StreamItem::Transaction(Transaction::Add(0, 1)),
+                StreamItem::Query(Query::Status(1)),
+                StreamItem::Transaction(Transaction::Close(0)),
+            ]
+        );
+        assert_eq!(read_all("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(read_all(r#"[{"Add":[0,1]}"#).is_err());
+        assert!(read_all(r#"[{"Add":[0,1]} {"Close":0}]"#).is_err());
+        assert!(read_all(r#"{"Add":[0,1]"#).is_err());
+        assert!(read_all(r#"{"Foo":0}"#).is_err());
+        assert!(read_all(r#"{"Close":"}"}"#).is_err());
+        // Stops at the first error
+        let mut reader =
+            StreamReader::new(r#"{"Foo":0} {"Close":0}"#.as_bytes());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_process_stream() {
+        let input = r#"{"Add":[0,1]} {"Close":0} {"Status":0} {"Status":5}
+            {"Close":1} {"Status":0}"#;
+        let mut graph = JumpStateGraph::new();
+        let mut out = Vec::new();
+        let reader = StreamReader::new(input.as_bytes());
+        let stats = process_stream(&mut graph, reader, &mut out).unwrap();
+        assert_eq!(stats, StreamStats { transactions: 3, queries: 3 });
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"vertex\":0,\"status\":\"Unknown\"}\n\
+            {\"vertex\":5,\"status\":\"Open\"}\n\
+            {\"vertex\":0,\"status\":\"Dead\"}\n"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+/*
+    Trace: replaying an example one transaction at a time
+
+    For debugging an algorithm on an example, rather than only looking at
+    its final output (see the trace binary). After each transaction, a
+    TraceStep records:
+    - the states whose status changed (see drain_events in interface.rs);
+    - the pairs of states that were merged into a single vertex (see
+      drain_merges, and merge_using in graph.rs);
+    - the debug time and space counters (see get_time and get_space; these
+      are only counted in debug mode, and are 0 otherwise). The time spent
+      draining the events is not counted.
+
+    A step involves a state if the state occurs in the transaction, changed
+    status, or was merged; this is used to filter the trace to the states
+    being watched.
+*/
+
+use super::driver::Algorithm;
+use super::example::Example;
+use super::interface::{StateGraph, StatusEvent, Transaction};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceStep {
+    pub step: usize,
+    pub transaction: Transaction,
+    pub events: Vec<StatusEvent>,
+    pub merges: Vec<(usize, usize)>,
+    // Counters after the transaction, and the time it took
+    pub time: usize,
+    pub time_delta: usize,
+    pub space: usize,
+}
+impl TraceStep {
+    pub fn involves(&self, v: usize) -> bool {
+        let in_transaction = match self.transaction {
+            Transaction::Add(v1, v2) | Transaction::Remove(v1, v2) => {
+                v1 == v || v2 == v
+            }
+            Transaction::Close(v1) | Transaction::Live(v1) => v1 == v,
+        };
+        in_transaction
+            || self.events.iter().any(|e| e.vertex == v)
+            || self.merges.iter().any(|&(v1, v2)| v1 == v || v2 == v)
+    }
+}
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}: {:?}", self.step, self.transaction)?;
+        for e in &self.events {
+            writeln!(f, "  {}: {:?} -> {:?}", e.vertex, e.old, e.new)?;
+        }
+        for (v1, v2) in &self.merges {
+            writeln!(f, "  merged {} and {}", v1, v2)?;
+        }
+        write!(
+            f,
+            "  time {} (+{}), space {}",
+            self.time, self.time_delta, self.space
+        )
+    }
+}
+
+pub struct Tracer {
+    graph: Box<dyn StateGraph>,
+    step: usize,
+    time: usize,
+}
+impl Tracer {
+    pub fn new(alg: Algorithm) -> Self {
+        let mut graph = alg.new_graph();
+        graph.enable_events();
+        Self { graph, step: 0, time: 0 }
+    }
+    pub fn process(&mut self, t: Transaction) -> TraceStep {
+        let time_before = self.graph.get_time();
+        self.graph.process(t);
+        let time_delta = self.graph.get_time() - time_before;
+        self.time += time_delta;
+        let result = TraceStep {
+            step: self.step,
+            transaction: t,
+            events: self.graph.drain_events(),
+            merges: self.graph.drain_merges(),
+            time: self.time,
+            time_delta,
+            space: self.graph.get_space(),
+        };
+        self.step += 1;
+        result
+    }
+    pub fn graph(&self) -> &dyn StateGraph {
+        self.graph.as_ref()
+    }
+}
+
+// Trace the first steps transactions of an example (all of them, if there
+// are fewer)
+pub fn trace(
+    example: &Example,
+    alg: Algorithm,
+    steps: usize,
+) -> Vec<TraceStep> {
+    let mut tracer = Tracer::new(alg);
+    example.input.0.iter().take(steps).map(|&t| tracer.process(t)).collect()
+}
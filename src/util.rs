@@ -43,6 +43,15 @@ where
     path.as_ref().exists()
 }
 
+pub fn modified_time<P>(path: P) -> SystemTime
+where
+    P: AsRef<Path> + Debug,
+{
+    path.as_ref().metadata().and_then(|m| m.modified()).unwrap_or_else(|err| {
+        panic!("Could not get modification time: {:?} -- {}", path, err)
+    })
+}
+
 pub fn from_json_file<P, T>(path: P) -> T
 where
     P: AsRef<Path> + Debug,
@@ -63,10 +72,19 @@ where
         panic!("Could not write JSON to {:?} -- {}", path, err)
     });
     writeln!(&mut writer).unwrap_or_else(|err| {
-        format!("Could not append newline to file: {:?} -- {}", &path, err);
+        panic!("Could not append newline to file: {:?} -- {}", &path, err);
     });
 }
 
+pub fn file_to_string<P>(path: P) -> String
+where
+    P: AsRef<Path> + Debug,
+{
+    fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!("Could not read file: {:?} -- {}", path, err)
+    })
+}
+
 pub fn lines_to_file<P>(path: P, lines: Vec<String>)
 where
     P: AsRef<Path> + Debug,
@@ -0,0 +1,70 @@
+/*
+    Test the C API (include/guided_digraph.h) on each algorithm.
+    Built and run by tests/test_ffi.rs; exits with a nonzero status
+    on the first failed check.
+*/
+
+#include <stdio.h>
+#include <stdlib.h>
+#include "guided_digraph.h"
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s (algorithm %u)\n", \
+                    __FILE__, __LINE__, #cond, alg);                    \
+            exit(1);                                                    \
+        }                                                               \
+    } while (0)
+
+static void test_algorithm(uint32_t alg) {
+    GidGraph *g = gid_new(alg);
+    CHECK(g != NULL);
+
+    /* Cycle 0 <-> 1 with an exit to 2 */
+    CHECK(gid_add_transition(g, 0, 1) == GID_OK);
+    CHECK(gid_add_transition(g, 1, 0) == GID_OK);
+    CHECK(gid_add_transition(g, 1, 2) == GID_OK);
+    CHECK(gid_mark_closed(g, 0) == GID_OK);
+    CHECK(gid_mark_closed(g, 1) == GID_OK);
+    CHECK(gid_get_status(g, 0) == GID_UNKNOWN);
+    CHECK(gid_get_status(g, 2) == GID_OPEN);
+    CHECK(!gid_is_dead(g, 0));
+    CHECK(gid_mark_closed(g, 2) == GID_OK);
+    CHECK(gid_is_dead(g, 0));
+    CHECK(gid_is_dead(g, 1));
+    CHECK(gid_get_status(g, 2) == GID_DEAD);
+
+    /* Updates to closed states are rejected */
+    CHECK(gid_add_transition(g, 0, 3) == GID_ERROR);
+    CHECK(gid_mark_closed(g, 1) == GID_ERROR);
+    CHECK(gid_mark_live(g, 2) == GID_ERROR);
+    CHECK(gid_get_status(g, 3) == GID_OPEN);
+
+    /* Live states */
+    CHECK(gid_add_transition(g, 3, 4) == GID_OK);
+    CHECK(gid_mark_closed(g, 3) == GID_OK);
+    CHECK(gid_mark_live(g, 4) == GID_OK);
+    CHECK(gid_get_status(g, 3) == GID_LIVE);
+    CHECK(gid_get_status(g, 4) == GID_LIVE);
+    CHECK(gid_mark_live(g, 4) == GID_OK);
+
+    gid_free(g);
+}
+
+int main(void) {
+    uint32_t alg = 99;
+    CHECK(gid_new(alg) == NULL);
+    CHECK(gid_get_status(NULL, 0) == GID_OPEN);
+    CHECK(gid_mark_closed(NULL, 0) == GID_ERROR);
+    gid_free(NULL);
+
+    uint32_t algs[] = {GID_ALG_NAIVE, GID_ALG_SIMPLE, GID_ALG_BFGT,
+                       GID_ALG_LOG, GID_ALG_JUMP};
+    for (size_t i = 0; i < sizeof(algs) / sizeof(algs[0]); i++) {
+        alg = algs[i];
+        test_algorithm(alg);
+    }
+    printf("C API tests passed\n");
+    return 0;
+}
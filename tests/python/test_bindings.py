@@ -0,0 +1,83 @@
+"""
+Test the Python bindings (src/python.rs).
+
+Build and install the module first (see pyproject.toml), then run from the
+repository root:
+    python3 -m unittest tests/python/test_bindings.py
+"""
+
+import unittest
+
+import guided_digraph as gid
+
+ALL_GRAPHS = [
+    gid.NaiveStateGraph,
+    gid.SimpleStateGraph,
+    gid.BFGTStateGraph,
+    gid.LogStateGraph,
+    gid.JumpStateGraph,
+]
+
+
+class TestStateGraph(unittest.TestCase):
+    def test_statuses(self):
+        for cls in ALL_GRAPHS:
+            g = cls()
+            # Cycle 0 <-> 1 with an exit to 2
+            g.add(0, 1)
+            g.add(1, 0)
+            g.add(1, 2)
+            g.close(0)
+            g.close(1)
+            self.assertEqual(g.status(0), "Unknown")
+            self.assertEqual(g.status(2), "Open")
+            self.assertEqual(g.dead_states(), [])
+            g.close(2)
+            self.assertEqual(g.dead_states(), [0, 1, 2])
+            self.assertTrue(g.is_dead(1))
+            # Separate component that becomes live
+            g.add(3, 4)
+            g.live(4)
+            self.assertEqual(g.status(3), "Live")
+            self.assertEqual(g.live_states(), [3, 4])
+            self.assertEqual(g.status(9), "Open")
+            self.assertEqual(len(g), 5)
+
+    def test_errors(self):
+        for cls in ALL_GRAPHS:
+            g = cls()
+            g.close(0)
+            with self.assertRaises(ValueError):
+                g.add(0, 1)
+            with self.assertRaises(ValueError):
+                g.close(0)
+            with self.assertRaises(ValueError):
+                g.live(0)
+            with self.assertRaises(OverflowError):
+                g.close(-1)
+
+
+class TestExample(unittest.TestCase):
+    def test_run(self):
+        ex = gid.Example.load("examples/handwritten/15")
+        self.assertEqual(ex.name, "examples/handwritten/15")
+        self.assertGreater(len(ex), 0)
+        expected = ex.expected()
+        for alg in ["naive", "simple", "bfgt", "log", "j"]:
+            result = ex.run(alg)
+            self.assertTrue(result["correct"])
+            self.assertFalse(result["timeout"])
+            self.assertEqual(result["output"], expected)
+            result = ex.run(alg, batch_size=10)
+            self.assertTrue(result["correct"])
+
+    def test_errors(self):
+        with self.assertRaises(FileNotFoundError):
+            gid.Example.load("examples/does_not_exist")
+        ex = gid.Example.load("examples/handwritten/15")
+        with self.assertRaises(ValueError):
+            ex.run("fastest")
+
+
+if __name__ == "__main__":
+    unittest.main()
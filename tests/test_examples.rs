@@ -3,7 +3,14 @@
 */
 
 use guided_digraph::constants::*;
-use guided_digraph::driver;
+use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::example::{Example, ExampleInput, ExampleResult};
+use guided_digraph::fuzz;
+use guided_digraph::interface::{Status, Transaction};
+use guided_digraph::trace::{self, Tracer};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /*
     Helper function
@@ -119,6 +126,12 @@ fn test_24() {
 fn test_25() {
     assert_one(EX_DIR_HANDWRITTEN, "25");
 }
+#[test]
+fn test_fuzz_911() {
+    // found by the fuzz binary: BFGT missed a cycle through a state that was
+    // merged by another transition from the same state
+    assert_one(EX_DIR_HANDWRITTEN, "fuzz_911");
+}
 
 /*
     Auto-generated examples
@@ -682,3 +695,360 @@ fn test_random_dense_1000_1() {
     assert_one(EX_DIR_RANDOM, "dense_1000_1_9");
     assert_one(EX_DIR_RANDOM, "dense_1000_1_10");
 }
+
+/*
+    Running examples in parallel
+*/
+
+#[test]
+fn test_run_compare_all_parallel() {
+    // Rows should be in the same order as in a serial run. Only the name
+    // and size are compared, since timings vary between runs (even the
+    // debug counters, for algorithms that iterate over hash maps).
+    let basenames = driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN);
+    let algs = driver::algs_all();
+    let prefixes = |jobs| -> Vec<String> {
+        driver::run_compare_all(&basenames, &algs, 10, None, jobs)
+            .iter()
+            .map(|row| row.split(", ").take(2).collect::<Vec<_>>().join(", "))
+            .collect()
+    };
+    let serial = prefixes(1);
+    assert_eq!(serial.len(), basenames.len());
+    assert_eq!(serial, prefixes(4));
+}
+
+/*
+    Preemptive timeouts
+*/
+
+// A line of n states, closed from the start, which is slow for Naive
+// (every closure recomputes the statuses of the whole line)
+fn slow_example(n: usize) -> Arc<Example> {
+    let mut input = ExampleInput::new();
+    for i in 0..n {
+        input.push(Transaction::Add(i, i + 1));
+    }
+    for i in 0..n {
+        input.push(Transaction::Close(i));
+    }
+    Arc::new(Example::new("slow", input, None))
+}
+
+#[test]
+fn test_run_preemptive() {
+    let example = slow_example(100);
+    let timeout = Duration::from_secs(10);
+    let result = driver::run_preemptive(
+        &example,
+        Algorithm::Naive,
+        timeout,
+        None,
+        false,
+    );
+    assert!(result.is_correct());
+    assert!(result.get_latency().is_none());
+    assert_eq!(result.get_output().unwrap().unknown.len(), 100);
+}
+
+#[test]
+fn test_run_preemptive_timeout() {
+    // As one batch, the whole example is a single step, which is longer than
+    // the timeout; without preemption it would run to completion
+    let example = slow_example(10000);
+    let batch_size = Some(example.len());
+    let timeout = Duration::from_millis(10);
+    let result = driver::run_preemptive(
+        &example,
+        Algorithm::Naive,
+        timeout,
+        batch_size,
+        false,
+    );
+    assert!(matches!(result, ExampleResult::Timeout(0)));
+    assert_eq!(result.summary(), "Timeout (after 0 transactions)");
+    // The next run doesn't wait for the abandoned worker
+    assert!(driver::abandoned_workers() >= 1);
+    let start = Instant::now();
+    let result = driver::run_preemptive(
+        &example,
+        Algorithm::Naive,
+        timeout,
+        batch_size,
+        false,
+    );
+    assert!(matches!(result, ExampleResult::Timeout(0)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(driver::abandoned_workers() >= 1);
+}
+
+/*
+    Latency of each kind of transaction
+*/
+
+#[test]
+fn test_run_preemptive_latency() {
+    let example = slow_example(100);
+    let timeout = Duration::from_secs(10);
+    let result =
+        driver::run_preemptive(&example, Algorithm::Jump, timeout, None, true);
+    assert!(result.is_correct());
+    let summaries =
+        result.get_latency().unwrap().summaries("slow", Algorithm::Jump);
+    let kinds: Vec<(&str, u64)> =
+        summaries.iter().map(|s| (s.kind.as_str(), s.count)).collect();
+    assert_eq!(kinds, vec![("Add", 100), ("Close", 100)]);
+    for s in &summaries {
+        assert!(s.p50_ns <= s.p99_ns && s.p99_ns <= s.max_ns);
+        let total: u64 = s.histogram.iter().map(|&(_, c)| c).sum();
+        assert_eq!(total, s.count);
+    }
+}
+
+#[test]
+fn test_run_latency() {
+    let algs = driver::algs_all();
+    let summaries = driver::run_latency("examples/handwritten/15", &algs, 10);
+    for alg in algs {
+        assert!(summaries.iter().any(|s| s.algorithm == alg.to_string()));
+    }
+}
+
+/*
+    Benchmarking with repeated runs
+*/
+
+#[test]
+fn test_run_bench() {
+    let algs = driver::algs_all();
+    let summaries =
+        driver::run_bench("examples/handwritten/15", &algs, 10, 1, 3);
+    assert_eq!(summaries.len(), algs.len());
+    for (summary, alg) in summaries.iter().zip(algs) {
+        assert_eq!(summary.algorithm, alg.to_string());
+        let stats = summary.stats.as_ref().unwrap();
+        assert_eq!(stats.count, 3);
+        assert!(stats.min <= stats.median && stats.median <= stats.max);
+    }
+}
+
+/*
+    Selecting a subset of the examples
+*/
+
+#[test]
+fn test_example_filter() {
+    let filter = driver::ExampleFilter {
+        dirs: vec![EX_DIR_HANDWRITTEN.to_string()],
+        patterns: vec![glob::Pattern::new("*/1?").unwrap()],
+        ..driver::ExampleFilter::new()
+    };
+    let selected = filter.select(ALL_EXAMPLE_DIRS);
+    assert_eq!(selected.len(), 1);
+    let (dir, basenames) = &selected[0];
+    assert_eq!(dir, EX_DIR_HANDWRITTEN);
+    assert_eq!(basenames.len(), 10);
+    // Sampling is deterministic given the seed, and keeps the order
+    let sample = |seed| driver::ExampleFilter {
+        sample: Some(4),
+        seed,
+        ..filter.clone()
+    };
+    let sampled = sample(1).select(ALL_EXAMPLE_DIRS);
+    assert_eq!(sampled, sample(1).select(ALL_EXAMPLE_DIRS));
+    let mut rest = basenames.iter();
+    assert_eq!(sampled[0].1.len(), 4);
+    for basename in &sampled[0].1 {
+        assert!(rest.any(|b| b == basename));
+    }
+    // Size bounds
+    let sized = driver::ExampleFilter {
+        min_size: Some(5),
+        max_size: Some(8),
+        ..filter.clone()
+    };
+    let sized = &sized.select(ALL_EXAMPLE_DIRS)[0].1;
+    assert!(!sized.is_empty() && sized.len() < basenames.len());
+    for basename in sized {
+        let size = Example::load_from(basename).len();
+        assert!((5..=8).contains(&size));
+    }
+}
+
+/*
+    Minimizing examples on which algorithms disagree (see fuzz.rs)
+*/
+
+#[test]
+fn test_drop_redundant() {
+    // Dropping redundant transactions doesn't change the output
+    let timeout = Duration::from_secs(10);
+    for basename in driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN) {
+        let example = Example::load_from(&basename);
+        let input = fuzz::drop_redundant(&example.input.0);
+        let dropped = Example::new(&basename, ExampleInput(input), None);
+        let output = |example: &Example| {
+            let mut graph = Algorithm::Naive.new_graph();
+            let result = example.run_with_timeout(graph.as_mut(), timeout);
+            let output = result.get_output().unwrap();
+            (output.live.clone(), output.dead.clone())
+        };
+        assert_eq!(output(&example), output(&dropped), "{}", basename);
+        assert!(!fuzz::outputs_differ(
+            &dropped.input.0,
+            Algorithm::Naive,
+            Algorithm::Jump,
+            timeout
+        ));
+    }
+}
+
+#[test]
+fn test_load_newer_input() {
+    // When an example has both a JSON and a compact input file, the newer
+    // one is loaded
+    let dir = std::env::temp_dir().join("gid_test_load_newer_input");
+    std::fs::create_dir_all(&dir).unwrap();
+    let basename = dir.join("ex").to_str().unwrap().to_string();
+    let json = format!("{}{}", basename, EXAMPLE_IN_EXT);
+    let bin = format!("{}{}", basename, EXAMPLE_IN_BIN_EXT);
+    let old = ExampleInput(vec![Transaction::Close(0)]);
+    let new = ExampleInput(vec![Transaction::Live(0)]);
+    let set_age = |path: &str, secs: u64| {
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        let time = std::time::SystemTime::now() - Duration::from_secs(secs);
+        file.set_modified(time).unwrap();
+    };
+    old.save_to_file(bin.as_ref());
+    new.save_to_file(json.as_ref());
+    set_age(&bin, 100);
+    set_age(&json, 10);
+    assert_eq!(Example::load_from(&basename).input.0, new.0);
+    set_age(&bin, 1);
+    assert_eq!(Example::load_from(&basename).input.0, old.0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_shrink_minimal() {
+    // Shrink inputs on which Naive disagrees with a "broken" Naive that
+    // ignores Live transactions; the result is 1-minimal
+    let output = |ts: &[Transaction]| {
+        let example = Example::new("", ExampleInput(ts.to_vec()), None);
+        let mut graph = Algorithm::Naive.new_graph();
+        let timeout = Duration::from_secs(10);
+        let result = example.run_with_timeout(graph.as_mut(), timeout);
+        let output = result.get_output().unwrap();
+        (output.live.clone(), output.dead.clone())
+    };
+    let differ = |ts: &[Transaction]| {
+        let broken: Vec<Transaction> = ts
+            .iter()
+            .copied()
+            .filter(|t| !matches!(t, Transaction::Live(_)))
+            .collect();
+        output(ts) != output(&broken)
+    };
+    let config = fuzz::FuzzConfig::default();
+    let mut shrunk_any = false;
+    for seed in 0..20 {
+        let input = fuzz::random_transactions(seed, &config);
+        let input = fuzz::drop_redundant(&input);
+        if !differ(&input) {
+            continue;
+        }
+        let shrunk = fuzz::shrink(&input, differ);
+        assert!(differ(&shrunk), "seed {}", seed);
+        assert!(shrunk.len() < input.len(), "seed {}", seed);
+        for i in 0..shrunk.len() {
+            let mut deleted = shrunk.clone();
+            deleted.remove(i);
+            assert!(!differ(&deleted), "seed {}: {:?}", seed, shrunk);
+        }
+        shrunk_any = true;
+    }
+    assert!(shrunk_any);
+}
+
+/*
+    Checking invariants after every transaction
+*/
+
+#[test]
+fn test_run_checked() {
+    for basename in driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN) {
+        let example = Example::load_from(&basename);
+        for alg in driver::algs_all() {
+            let result = driver::run_checked(&example, alg);
+            assert_eq!(result, Ok(()), "{} ({})", basename, alg);
+        }
+    }
+    let example = Example::load_from("examples/generated/unkloop_100");
+    for alg in driver::algs_all() {
+        assert_eq!(driver::run_checked(&example, alg), Ok(()));
+    }
+}
+
+#[test]
+fn test_dot_after() {
+    let example = Example::load_from("examples/generated/unkloop_100");
+    for alg in driver::algs_all() {
+        let empty = driver::dot_after(&example, alg, 0);
+        assert_eq!(empty, "digraph {\n    node [style=filled];\n}");
+        // Output is deterministic, and includes each state seen so far
+        let dot = driver::dot_after(&example, alg, 12);
+        assert_eq!(dot, driver::dot_after(&example, alg, 12));
+        assert!(dot.contains("\"6\" [label="), "{}", alg);
+        assert!(!dot.contains("\"7\" [label="), "{}", alg);
+        assert!(dot.contains("\"0\" -> \"100\";"), "{}", alg);
+    }
+    let dot = driver::dot_after(&example, Algorithm::Jump, 12);
+    assert!(dot.contains("label=\"jump 0\""));
+    let dot = driver::dot_after(&example, Algorithm::Log, 12);
+    assert!(dot.contains("label=\"next\""));
+    let dot = driver::dot_after(&example, Algorithm::BFGT, 12);
+    assert!(dot.contains("level 0"));
+}
+
+#[test]
+fn test_trace() {
+    // Applying the status changes in the trace gives the final statuses
+    for basename in driver::example_basenames_in_dir(EX_DIR_HANDWRITTEN) {
+        let example = Example::load_from(&basename);
+        for alg in driver::algs_all() {
+            let mut tracer = Tracer::new(alg);
+            let mut statuses = HashMap::new();
+            for &t in &example.input.0 {
+                for e in tracer.process(t).events {
+                    let old = statuses.insert(e.vertex, e.new);
+                    assert_eq!(old.unwrap_or(Status::Open), e.old);
+                }
+            }
+            for v in example.input.get_states() {
+                let status = tracer.graph().get_status(v);
+                let traced = statuses.get(&v).copied();
+                assert_eq!(
+                    status.unwrap_or(Status::Open),
+                    traced.unwrap_or(Status::Open),
+                    "{} ({}), state {}",
+                    basename,
+                    alg,
+                    v
+                );
+            }
+        }
+    }
+    // Closing state 1 merges the cycle through 0, 1, 4, 5, 8, and 9
+    let example = Example::load_from("examples/handwritten/fuzz_911");
+    let steps = trace::trace(&example, Algorithm::Jump, 100);
+    assert_eq!(steps.len(), 14);
+    let last = &steps[13];
+    assert_eq!(last.transaction, Transaction::Close(1));
+    assert_eq!(last.events.len(), 6);
+    assert!(last.events.iter().all(|e| e.new == Status::Dead));
+    assert_eq!(last.merges.len(), 5);
+    assert!(last.involves(9) && !last.involves(2));
+    assert!(steps[..13].iter().all(|step| step.merges.is_empty()));
+    let steps = trace::trace(&example, Algorithm::Naive, 100);
+    assert!(steps.iter().all(|step| step.merges.is_empty()));
+}
@@ -0,0 +1,82 @@
+/*
+    Test the C API (src/ffi.rs): check that the generated header is up to
+    date, and build and run the C test program against the static library.
+*/
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const HEADER: &str = "include/guided_digraph.h";
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn generate_header() -> String {
+    let config =
+        cbindgen::Config::from_file(manifest_dir().join("cbindgen.toml"))
+            .expect("Could not read cbindgen.toml");
+    let mut result = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(manifest_dir())
+        .with_config(config)
+        .generate()
+        .expect("Could not generate header")
+        .write(&mut result);
+    String::from_utf8(result).unwrap()
+}
+
+// The directory with the library built for this test (target/debug/deps)
+fn deps_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn test_header_up_to_date() {
+    let header = generate_header();
+    let path = manifest_dir().join(HEADER);
+    if env::var_os("GID_UPDATE_HEADER").is_some() {
+        fs::write(&path, &header).unwrap();
+    }
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        existing == header,
+        "{} is out of date; rerun with GID_UPDATE_HEADER=1 to regenerate it",
+        HEADER
+    );
+}
+
+#[test]
+fn test_c_program() {
+    let lib = deps_dir().join("libguided_digraph.a");
+    assert!(lib.exists(), "Static library not found: {:?}", lib);
+    let exe = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("test_ffi_c");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let mut compile = Command::new(&cc);
+    compile
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir().join("include"))
+        .arg(manifest_dir().join("tests/ffi/test_ffi.c"))
+        .arg(&lib)
+        .arg("-o")
+        .arg(&exe);
+    if cfg!(target_os = "linux") {
+        compile.args(["-lpthread", "-ldl", "-lm"]);
+    }
+    let status = compile.status().unwrap_or_else(|err| {
+        panic!("Could not run C compiler {} -- {}", cc, err)
+    });
+    assert!(status.success(), "Could not compile tests/ffi/test_ffi.c");
+    let output = Command::new(&exe).output().unwrap();
+    assert!(
+        output.status.success(),
+        "C test program failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
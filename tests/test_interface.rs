@@ -0,0 +1,767 @@
+/*
+    Test the StateGraph interface directly on each algorithm
+    (rather than through the example files in examples/).
+*/
+
+use guided_digraph::algorithm::{
+    BFGTStateGraph, JumpStateGraph, LogStateGraph, NaiveStateGraph,
+    SimpleStateGraph,
+};
+use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::fuzz::{self, FuzzConfig};
+use guided_digraph::interface::{
+    StateGraph, Status, StatusEvent, Transaction, Vertex,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
+
+/*
+    Helpers
+*/
+
+// Run a check on a new graph of each of the algorithms
+fn for_each_alg<V: Vertex + 'static>(
+    algs: &[Algorithm],
+    check: impl Fn(&mut dyn StateGraph<V>),
+) {
+    for alg in algs {
+        // Only shown if the check fails
+        println!("Algorithm: {}", alg);
+        check(alg.new_graph_of().as_mut());
+    }
+}
+
+// Check that g agrees with expected on the states 0..states (as in
+// example.rs, unseen states count as Open)
+fn assert_same_statuses(
+    expected: &dyn StateGraph,
+    g: &dyn StateGraph,
+    states: usize,
+    context: &str,
+) {
+    for v in 0..states {
+        assert_eq!(
+            expected.get_status(v).unwrap_or(Status::Open),
+            g.get_status(v).unwrap_or(Status::Open),
+            "vertex {}, {}",
+            v,
+            context
+        );
+    }
+}
+
+/*
+    Generic vertex types
+*/
+
+// States named by pairs, as in a product automaton
+type Pair = (usize, char);
+
+fn assert_pairs(g: &mut dyn StateGraph<Pair>) {
+    // Cycle (0, a) <-> (1, a) with an exit to (2, b)
+    g.add_transition((0, 'a'), (1, 'a'));
+    g.add_transition((1, 'a'), (0, 'a'));
+    g.add_transition((1, 'a'), (2, 'b'));
+    g.mark_closed((0, 'a'));
+    g.mark_closed((1, 'a'));
+    assert_eq!(g.get_status((0, 'a')), Some(Status::Unknown));
+    assert_eq!(g.get_status((1, 'a')), Some(Status::Unknown));
+    assert_eq!(g.get_status((2, 'b')), Some(Status::Open));
+    g.mark_closed((2, 'b'));
+    assert_eq!(g.get_status((0, 'a')), Some(Status::Dead));
+    assert_eq!(g.get_status((1, 'a')), Some(Status::Dead));
+    assert_eq!(g.get_status((2, 'b')), Some(Status::Dead));
+    // Separate component that becomes live
+    g.add_transition((3, 'a'), (4, 'b'));
+    g.mark_live((4, 'b'));
+    assert_eq!(g.get_status((3, 'a')), Some(Status::Live));
+    assert_eq!(g.get_status((4, 'b')), Some(Status::Live));
+    assert_eq!(g.get_status((5, 'c')), None);
+}
+
+fn assert_strs(g: &mut dyn StateGraph<&'static str>) {
+    g.add_transition("init", "left");
+    g.add_transition("init", "right");
+    g.mark_closed("init");
+    g.mark_closed("left");
+    assert_eq!(g.get_status("init"), Some(Status::Unknown));
+    assert_eq!(g.get_status("left"), Some(Status::Dead));
+    g.mark_closed("right");
+    assert_eq!(g.get_status("init"), Some(Status::Dead));
+}
+
+#[test]
+fn test_pairs() {
+    for_each_alg(driver::ALL_ALGS, assert_pairs);
+}
+#[test]
+fn test_strs() {
+    for_each_alg(driver::ALL_ALGS, assert_strs);
+}
+
+/*
+    Removing transitions
+*/
+
+fn assert_remove(g: &mut dyn StateGraph) {
+    g.enable_certificates();
+    // Unknown states become dead
+    g.add_transition(0, 1);
+    g.add_transition(1, 2);
+    g.mark_closed(0);
+    g.mark_closed(1);
+    assert_eq!(g.get_status(0), Some(Status::Unknown));
+    g.remove_transition(1, 2);
+    assert_eq!(g.get_status(0), Some(Status::Dead));
+    assert_eq!(g.get_status(1), Some(Status::Dead));
+    assert_eq!(g.get_status(2), Some(Status::Open));
+    // Live states become dead, including closures skipped while Live
+    g.add_transition(3, 4);
+    g.add_transition(4, 5);
+    g.mark_live(5);
+    g.mark_closed(3);
+    g.mark_closed(4);
+    assert_eq!(g.get_status(3), Some(Status::Live));
+    g.remove_transition(4, 5);
+    assert_eq!(g.get_status(3), Some(Status::Dead));
+    assert_eq!(g.get_status(4), Some(Status::Dead));
+    assert_eq!(g.get_status(5), Some(Status::Live));
+    // Transitions from Live states are kept too
+    g.add_transition(6, 7);
+    g.mark_live(7);
+    g.add_transition(6, 8);
+    g.remove_transition(6, 7);
+    assert_eq!(g.get_status(6), Some(Status::Open));
+    g.mark_live(8);
+    assert_eq!(g.get_status(6), Some(Status::Live));
+    // Splitting a cycle that may have been merged
+    g.add_transition(10, 11);
+    g.add_transition(11, 10);
+    g.add_transition(11, 12);
+    g.mark_closed(10);
+    g.mark_closed(11);
+    g.remove_transition(11, 10);
+    assert_eq!(g.get_status(10), Some(Status::Unknown));
+    assert_eq!(g.get_status(11), Some(Status::Unknown));
+    g.remove_transition(11, 12);
+    assert_eq!(g.get_status(10), Some(Status::Dead));
+    assert_eq!(g.get_status(11), Some(Status::Dead));
+    // Removed transitions are not in certificates
+    let mut cert = g.dead_certificate(10).unwrap();
+    cert.sort_unstable();
+    assert_eq!(cert, vec![10, 11]);
+    // Transitions from Live states to states not seen yet can be removed
+    g.add_transition(13, 14);
+    g.mark_live(14);
+    g.add_transition(13, 15);
+    g.remove_transition(13, 15);
+    g.mark_live(15);
+    g.remove_transition(13, 14);
+    assert_eq!(g.get_status(13), Some(Status::Open));
+    assert_remove_into_merged(g);
+}
+
+// Removing one of two transitions into states that were merged keeps the
+// other one (supported by Log too)
+fn assert_remove_into_merged(g: &mut dyn StateGraph) {
+    g.add_transition(21, 23);
+    g.add_transition(21, 22);
+    g.add_transition(22, 21);
+    g.mark_closed(21);
+    g.mark_closed(22);
+    g.add_transition(20, 21);
+    g.add_transition(20, 22);
+    g.mark_closed(20);
+    g.remove_transition(20, 21);
+    assert_eq!(g.get_status(20), Some(Status::Unknown));
+    g.mark_closed(23);
+    for v in 20..24 {
+        assert_eq!(g.get_status(v), Some(Status::Dead));
+    }
+    let mut cert = g.dead_certificate(20).unwrap();
+    cert.sort_unstable();
+    assert_eq!(cert, vec![20, 21, 22, 23]);
+}
+
+#[test]
+fn test_remove() {
+    let algs: Vec<Algorithm> = driver::ALL_ALGS
+        .iter()
+        .copied()
+        .filter(|alg| alg.supports_remove())
+        .collect();
+    for_each_alg(&algs, assert_remove);
+}
+
+#[test]
+fn test_remove_log() {
+    let mut g = LogStateGraph::new();
+    g.enable_certificates();
+    // From an Open state
+    g.add_transition(0, 1);
+    g.add_transition(0, 2);
+    g.remove_transition(0, 1);
+    g.mark_closed(2);
+    g.mark_closed(0);
+    assert_eq!(g.get_status(0), Some(Status::Dead));
+    assert_eq!(g.get_status(1), Some(Status::Open));
+    // Successor edge, with a chain of states pointing to it
+    g.add_transition(3, 4);
+    g.add_transition(4, 5);
+    g.add_transition(4, 6);
+    g.mark_closed(3);
+    g.mark_closed(4);
+    g.mark_closed(5);
+    assert_eq!(g.get_status(3), Some(Status::Unknown));
+    assert_eq!(g.get_status(4), Some(Status::Unknown));
+    g.remove_transition(4, 6);
+    assert_eq!(g.get_status(3), Some(Status::Dead));
+    assert_eq!(g.get_status(4), Some(Status::Dead));
+    // Successor edge with another way out
+    g.add_transition(7, 8);
+    g.add_transition(7, 9);
+    g.add_transition(8, 9);
+    g.mark_closed(7);
+    g.mark_closed(8);
+    g.remove_transition(7, 9);
+    g.remove_transition(7, 8);
+    assert_eq!(g.get_status(7), Some(Status::Dead));
+    assert_eq!(g.get_status(8), Some(Status::Unknown));
+    assert_remove_into_merged(&mut g);
+}
+
+#[test]
+#[should_panic]
+fn test_remove_log_live() {
+    let mut g = LogStateGraph::new();
+    g.add_transition(0, 1);
+    g.mark_live(1);
+    g.remove_transition(0, 1);
+}
+
+#[test]
+#[should_panic]
+fn test_remove_log_merged() {
+    let mut g = LogStateGraph::new();
+    g.add_transition(0, 1);
+    g.add_transition(1, 2);
+    g.add_transition(1, 0);
+    g.mark_closed(0);
+    g.mark_closed(1);
+    g.remove_transition(1, 2);
+}
+
+#[test]
+#[should_panic]
+fn test_remove_jump() {
+    let mut g = JumpStateGraph::new();
+    g.add_transition(0, 1);
+    g.remove_transition(0, 1);
+}
+
+/*
+    Differential fuzzing of all algorithms (see fuzz.rs)
+*/
+
+fn assert_fuzz(config: FuzzConfig) {
+    if let Some(failure) = fuzz::fuzz(0..300, &config) {
+        panic!(
+            "seed {}, shrunk to {:?}\n{}",
+            failure.seed, failure.shrunk, failure.failure
+        );
+    }
+}
+
+#[test]
+fn test_fuzz() {
+    assert_fuzz(FuzzConfig::default());
+    assert_fuzz(FuzzConfig { states: 30, len: 200, remove: false });
+}
+#[test]
+fn test_fuzz_remove() {
+    assert_fuzz(FuzzConfig { remove: true, ..FuzzConfig::default() });
+    assert_fuzz(FuzzConfig { states: 12, len: 200, remove: true });
+}
+#[test]
+fn test_fuzz_remove_log() {
+    // Large enough to find removals into states merged with others
+    let config = FuzzConfig { states: 8, len: 100, remove: true };
+    if let Some(failure) = fuzz::fuzz_log_remove(0..300, &config) {
+        panic!(
+            "seed {}, shrunk to {:?}\n{}",
+            failure.seed, failure.shrunk, failure.failure
+        );
+    }
+}
+
+/*
+    Checkpoints (push/pop)
+*/
+
+fn assert_push_pop(g: &mut dyn StateGraph) {
+    g.add_transition(0, 1);
+    g.add_transition(1, 2);
+    g.add_transition(2, 0);
+    g.add_transition(2, 3);
+    g.mark_closed(0);
+    g.push();
+    // Merges a cycle and then kills it
+    g.mark_closed(1);
+    g.mark_closed(2);
+    g.mark_closed(3);
+    assert_eq!(g.get_status(0), Some(Status::Dead));
+    g.push();
+    g.add_transition(4, 5);
+    g.mark_live(5);
+    assert_eq!(g.get_status(4), Some(Status::Live));
+    g.pop();
+    assert_eq!(g.get_status(4), None);
+    assert_eq!(g.get_status(0), Some(Status::Dead));
+    g.pop();
+    assert_eq!(g.get_status(0), Some(Status::Unknown));
+    assert_eq!(g.get_status(1), Some(Status::Open));
+    assert_eq!(g.get_status(2), Some(Status::Open));
+    // The restored graph is still usable, and this time 3 becomes live
+    g.mark_live(3);
+    g.mark_closed(1);
+    g.mark_closed(2);
+    assert_eq!(g.get_status(0), Some(Status::Live));
+    assert_eq!(g.get_status(1), Some(Status::Live));
+}
+
+#[test]
+fn test_push_pop() {
+    for_each_alg(driver::ALL_ALGS, assert_push_pop);
+}
+
+#[test]
+#[should_panic]
+fn test_pop_without_push() {
+    let mut g = JumpStateGraph::new();
+    g.add_transition(0, 1);
+    g.pop();
+}
+
+// Random transactions (see fuzz.rs) with pushes and pops in between,
+// comparing each algorithm against Naive replaying the transactions from
+// scratch after each pop. (Popping only reopens states, so the rest of the
+// transactions stay valid.)
+#[test]
+fn test_push_pop_random() {
+    let config = FuzzConfig { states: 12, len: 150, remove: false };
+    for seed in 0..30 {
+        let transactions = fuzz::random_transactions(seed, &config);
+        for_each_alg(driver::ALL_ALGS, |g| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut expected = NaiveStateGraph::new();
+            // Transactions so far, and the number of them at each push
+            let mut history = Vec::new();
+            let mut scopes = Vec::new();
+            for &t in &transactions {
+                match rng.gen_range(0..5) {
+                    0 => {
+                        scopes.push(history.len());
+                        g.push();
+                    }
+                    1 if !scopes.is_empty() => {
+                        history.truncate(scopes.pop().unwrap());
+                        g.pop();
+                        expected = NaiveStateGraph::new();
+                        history.iter().for_each(|&t| expected.process(t));
+                    }
+                    _ => (),
+                }
+                history.push(t);
+                expected.process(t);
+                g.process(t);
+                let context = format!("seed {}, after {:?}", seed, t);
+                assert_same_statuses(&expected, g, config.states, &context);
+            }
+        });
+    }
+}
+
+/*
+    Status change events
+*/
+
+fn assert_events(g: &mut dyn StateGraph) {
+    g.add_transition(0, 1);
+    g.mark_closed(0);
+    // Existing statuses are not reported
+    g.enable_events();
+    assert_eq!(g.drain_events(), vec![]);
+    g.add_transition(1, 2);
+    g.mark_closed(1);
+    assert_eq!(
+        g.drain_events(),
+        vec![StatusEvent {
+            vertex: 1,
+            old: Status::Open,
+            new: Status::Unknown
+        }]
+    );
+    g.mark_closed(2);
+    let mut events = g.drain_events();
+    events.sort_by_key(|e| e.vertex);
+    assert_eq!(
+        events,
+        vec![
+            StatusEvent { vertex: 0, old: Status::Unknown, new: Status::Dead },
+            StatusEvent { vertex: 1, old: Status::Unknown, new: Status::Dead },
+            StatusEvent { vertex: 2, old: Status::Open, new: Status::Dead },
+        ]
+    );
+    assert_eq!(g.drain_events(), vec![]);
+    g.add_transition(3, 4);
+    g.mark_live(4);
+    let mut events = g.drain_events();
+    events.sort_by_key(|e| e.vertex);
+    assert_eq!(
+        events,
+        vec![
+            StatusEvent { vertex: 3, old: Status::Open, new: Status::Live },
+            StatusEvent { vertex: 4, old: Status::Open, new: Status::Live },
+        ]
+    );
+}
+
+#[test]
+fn test_events() {
+    for_each_alg(driver::ALL_ALGS, assert_events);
+}
+
+#[test]
+fn test_events_push_pop() {
+    let mut g = SimpleStateGraph::new();
+    g.enable_events();
+    g.push();
+    g.mark_closed(0);
+    assert_eq!(
+        g.drain_events(),
+        vec![StatusEvent { vertex: 0, old: Status::Open, new: Status::Dead }]
+    );
+    g.pop();
+    assert_eq!(
+        g.drain_events(),
+        vec![StatusEvent { vertex: 0, old: Status::Dead, new: Status::Open }]
+    );
+}
+
+// Replaying the drained events must give the current statuses, also across
+// pushes and pops
+#[test]
+fn test_events_random() {
+    let config = FuzzConfig { states: 12, len: 150, remove: false };
+    for seed in 0..30 {
+        let transactions = fuzz::random_transactions(seed, &config);
+        for_each_alg(driver::ALL_ALGS, |g| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            g.enable_events();
+            let mut replayed = HashMap::new();
+            let mut depth = 0;
+            for &t in &transactions {
+                match rng.gen_range(0..5) {
+                    0 => {
+                        depth += 1;
+                        g.push();
+                    }
+                    1 if depth > 0 => {
+                        depth -= 1;
+                        g.pop();
+                    }
+                    _ => (),
+                }
+                g.process(t);
+                for e in g.drain_events() {
+                    let old = replayed.insert(e.vertex, e.new);
+                    assert_eq!(old.unwrap_or(Status::Open), e.old);
+                    assert_ne!(e.old, e.new);
+                }
+                for v in 0..config.states {
+                    assert_eq!(
+                        g.get_status(v).unwrap_or(Status::Open),
+                        replayed.get(&v).copied().unwrap_or(Status::Open),
+                        "seed {}, vertex {}",
+                        seed,
+                        v,
+                    );
+                }
+            }
+        });
+    }
+}
+
+/*
+    Certificates
+*/
+
+fn assert_certificates(g: &mut dyn StateGraph) {
+    g.add_transition(0, 1);
+    g.add_transition(1, 2);
+    g.add_transition(2, 1);
+    g.add_transition(3, 0);
+    g.mark_closed(1);
+    g.mark_closed(2);
+    g.mark_closed(0);
+    // The index of transitions includes those added before it was enabled
+    g.enable_certificates();
+    let mut cert = g.dead_certificate(0).unwrap();
+    cert.sort_unstable();
+    assert_eq!(cert, vec![0, 1, 2]);
+    assert_eq!(g.dead_certificate(3), None);
+    assert_eq!(g.live_certificate(0), None);
+
+    g.add_transition(4, 5);
+    g.add_transition(5, 6);
+    g.add_transition(6, 5);
+    g.add_transition(6, 7);
+    g.add_transition(5, 7);
+    g.add_transition(7, 8);
+    g.mark_closed(5);
+    g.mark_closed(6);
+    assert_eq!(g.live_certificate(4), None);
+    g.mark_live(8);
+    assert_eq!(g.live_certificate(4), Some(vec![4, 5, 7, 8]));
+    assert_eq!(g.live_certificate(8), Some(vec![8]));
+    assert_eq!(g.dead_certificate(4), None);
+}
+
+#[test]
+fn test_certificates() {
+    for_each_alg(driver::ALL_ALGS, assert_certificates);
+}
+
+// Check each certificate against the transactions that were processed
+#[test]
+fn test_certificates_random() {
+    let config = FuzzConfig { states: 12, len: 100, remove: false };
+    for seed in 0..30 {
+        let transactions = fuzz::random_transactions(seed, &config);
+        let mut edges = HashSet::new();
+        let mut closed = HashSet::new();
+        let mut live = HashSet::new();
+        for &t in &transactions {
+            match t {
+                Transaction::Add(v1, v2) => {
+                    edges.insert((v1, v2));
+                }
+                Transaction::Close(v) => {
+                    closed.insert(v);
+                }
+                Transaction::Live(v) => {
+                    live.insert(v);
+                }
+                Transaction::Remove(_, _) => unreachable!(),
+            }
+        }
+        for_each_alg(driver::ALL_ALGS, |g| {
+            g.enable_certificates();
+            transactions.iter().for_each(|&t| g.process(t));
+            for v in 0..config.states {
+                let dead = g.dead_certificate(v);
+                assert_eq!(dead.is_some(), g.is_dead(v));
+                if let Some(cert) = dead {
+                    let cert: HashSet<usize> = cert.into_iter().collect();
+                    assert!(cert.contains(&v));
+                    for &u in &cert {
+                        assert!(closed.contains(&u) && !live.contains(&u));
+                    }
+                    for &(u, w) in &edges {
+                        assert!(!cert.contains(&u) || cert.contains(&w));
+                    }
+                }
+                let path = g.live_certificate(v);
+                assert_eq!(path.is_some(), g.is_live(v));
+                if let Some(path) = path {
+                    assert_eq!(path[0], v);
+                    assert!(live.contains(path.last().unwrap()));
+                    for pair in path.windows(2) {
+                        assert!(edges.contains(&(pair[0], pair[1])));
+                    }
+                }
+            }
+        });
+    }
+}
+
+/*
+    Concurrent wrapper
+*/
+
+#[test]
+fn test_concurrent_is_sync() {
+    use guided_digraph::concurrent::ConcurrentStateGraph;
+    fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<ConcurrentStateGraph<NaiveStateGraph>>();
+    assert_sync::<ConcurrentStateGraph<SimpleStateGraph>>();
+    assert_sync::<ConcurrentStateGraph<BFGTStateGraph>>();
+    assert_sync::<ConcurrentStateGraph<LogStateGraph>>();
+    assert_sync::<ConcurrentStateGraph<JumpStateGraph>>();
+}
+
+#[test]
+fn test_concurrent_readers() {
+    use guided_digraph::concurrent::ConcurrentStateGraph;
+    use guided_digraph::interface::Transaction;
+    use std::thread;
+
+    // One writer adds a chain of dead states n, n - 1, ..., 0 in batches,
+    // while readers check that the dead states always form a suffix
+    let n = 1000;
+    let g: ConcurrentStateGraph<JumpStateGraph> = ConcurrentStateGraph::new();
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                while !g.is_dead(0) {
+                    for v in 0..n {
+                        // Once a state is dead, everything before it is too
+                        if g.is_dead(v) {
+                            assert!(g.is_dead(v + 1));
+                        }
+                    }
+                }
+            });
+        }
+        s.spawn(|| {
+            g.process(Transaction::Close(n));
+            for chunk in (0..n).rev().collect::<Vec<_>>().chunks(10) {
+                let batch: Vec<Transaction> = chunk
+                    .iter()
+                    .flat_map(|&v| {
+                        [Transaction::Add(v, v + 1), Transaction::Close(v)]
+                    })
+                    .collect();
+                g.process_batch(&batch);
+            }
+        });
+    });
+    assert_eq!(g.get_status(n + 1), None);
+    for v in 0..=n {
+        assert_eq!(g.get_status(v), Some(Status::Dead));
+    }
+    let mut inner = g.into_inner();
+    assert!(inner.is_dead(0));
+    assert!(inner.drain_events().is_empty());
+}
+
+// Transactions that the wrapped graph skips (e.g. an Add from a Live state)
+// don't make states seen; Simple keeps them for removals, and Jump doesn't
+fn assert_concurrent_skipped<G: StateGraph>() {
+    use guided_digraph::concurrent::ConcurrentStateGraph;
+
+    let g: ConcurrentStateGraph<G> = ConcurrentStateGraph::new();
+    g.process_batch(&[
+        Transaction::Live(0),
+        Transaction::Add(0, 1),
+        Transaction::Add(2, 2),
+    ]);
+    assert_eq!(g.get_status(0), Some(Status::Live));
+    let statuses: Vec<Option<Status>> =
+        (0..3).map(|v| g.get_status(v)).collect();
+    let inner = g.into_inner();
+    for (v, &st) in statuses.iter().enumerate() {
+        assert_eq!(st, inner.get_status(v));
+    }
+    assert_eq!(statuses[2], None);
+}
+#[test]
+fn test_concurrent_skipped() {
+    assert_concurrent_skipped::<SimpleStateGraph>();
+    assert_concurrent_skipped::<JumpStateGraph>();
+}
+
+/*
+    Batches of transactions
+*/
+
+// Processing random transactions (see fuzz.rs) in random batches should give
+// the same statuses as processing them one at a time
+#[test]
+fn test_process_batch_random() {
+    let config = FuzzConfig { states: 20, len: 150, remove: false };
+    for seed in 0..100 {
+        let transactions = fuzz::random_transactions(seed, &config);
+        for_each_alg(driver::ALL_ALGS, |g| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut expected = NaiveStateGraph::new();
+            let mut rest = &transactions[..];
+            while !rest.is_empty() {
+                let size = rng.gen_range(0..30).min(rest.len());
+                let (batch, tail) = rest.split_at(size);
+                batch.iter().for_each(|&t| expected.process(t));
+                g.process_batch(batch);
+                let context = format!("seed {}, batch {:?}", seed, batch);
+                assert_same_statuses(&expected, g, config.states, &context);
+                rest = tail;
+            }
+        });
+    }
+}
+
+/*
+    Default implementations of the optional functions
+*/
+
+// Implements only the required functions, by delegating to Naive
+struct Minimal(NaiveStateGraph);
+impl StateGraph for Minimal {
+    fn new() -> Self {
+        Minimal(NaiveStateGraph::new())
+    }
+    fn add_transition_unchecked(&mut self, v1: usize, v2: usize) {
+        self.0.add_transition_unchecked(v1, v2);
+    }
+    fn mark_closed_unchecked(&mut self, v: usize) {
+        self.0.mark_closed_unchecked(v);
+    }
+    fn mark_live_unchecked(&mut self, v: usize) {
+        self.0.mark_live_unchecked(v);
+    }
+    fn get_status(&self, v: usize) -> Option<Status> {
+        self.0.get_status(v)
+    }
+    fn get_space(&self) -> usize {
+        self.0.get_space()
+    }
+    fn get_time(&self) -> usize {
+        self.0.get_time()
+    }
+}
+
+#[test]
+fn test_minimal_defaults() {
+    let mut g = Minimal::new();
+    g.add_transition(0, 1);
+    g.mark_closed_batch(&[0, 1]);
+    assert_eq!(g.get_status(0), Some(Status::Dead));
+    assert_eq!(g.drain_events(), vec![]);
+    assert_eq!(g.drain_merges(), vec![]);
+    assert_eq!(g.check_invariants(), Ok(()));
+    assert!(!g.can_process(Transaction::Add(0, 2)));
+    assert!(!g.can_process(Transaction::Close(1)));
+    assert!(g.can_process(Transaction::Live(2)));
+    assert!(g.can_process(Transaction::Remove(0, 1)));
+    assert_eq!(Status::Dead.to_string(), "Dead");
+}
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_minimal_events() {
+    Minimal::new().enable_events();
+}
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_minimal_certificates() {
+    Minimal::new().dead_certificate(0);
+}
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_minimal_dot() {
+    Minimal::new().to_dot();
+}
+#[test]
+#[should_panic(expected = "not supported")]
+fn test_minimal_push() {
+    Minimal::new().push();
+}
@@ -0,0 +1,58 @@
+/*
+    Test memory tracking (src/memory.rs), with the tracking allocator
+    installed for this test binary.
+*/
+
+use guided_digraph::driver::{self, Algorithm};
+use guided_digraph::memory::{self, PeakMemory, TrackingAllocator};
+use std::hint::black_box;
+use std::thread;
+
+#[global_allocator]
+static ALLOC: TrackingAllocator = TrackingAllocator;
+
+const MB: usize = 1 << 20;
+
+#[test]
+fn test_peak_memory() {
+    let _ = black_box(vec![0u8; 16]);
+    assert!(memory::is_tracking());
+    let measurement = PeakMemory::start();
+    assert!(measurement.bytes().unwrap() < MB);
+    let v: Vec<u8> = black_box(Vec::with_capacity(4 * MB));
+    drop(v);
+    let peak = measurement.bytes().unwrap();
+    assert!((4 * MB..5 * MB).contains(&peak), "peak: {}", peak);
+    // Still the peak after the memory is freed
+    assert_eq!(measurement.bytes().unwrap(), peak);
+}
+
+#[test]
+fn test_peak_memory_per_thread() {
+    // Allocations on other threads don't count
+    let measurement = PeakMemory::start();
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| black_box(vec![1u8; 4 * MB]));
+        }
+    });
+    assert!(measurement.bytes().unwrap() < MB);
+    let v = black_box(vec![1u8; 2 * MB]);
+    assert!(measurement.bytes().unwrap() >= 2 * MB);
+    drop(v);
+}
+
+#[test]
+fn test_space_column() {
+    // In release mode, space comes from the tracking allocator
+    let header = driver::run_compare_csv_header(&[Algorithm::Jump]);
+    assert_eq!(header, "name, size, timeout, time (jump), space (jump)");
+    let row = driver::run_compare(
+        "examples/handwritten/15",
+        &[Algorithm::Jump],
+        10,
+        None,
+    );
+    let space = row.split(", ").last().unwrap();
+    assert!(space.parse::<usize>().unwrap() > 0, "row: {}", row);
+}